@@ -0,0 +1,121 @@
+// Command-line arguments, read from flags or environment variables
+
+use clap::{Parser, Subcommand};
+use solana_sdk::pubkey::Pubkey;
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(name = "soldrip-cli", about = "Operate a SolDrip deployment")]
+pub struct Cli {
+    /// RPC endpoint, e.g. http://127.0.0.1:8899 for solana-test-validator
+    #[arg(long, env = "SOLDRIP_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    pub rpc_url: String,
+
+    /// Keypair that signs and pays for transactions
+    #[arg(long, env = "SOLDRIP_KEYPAIR", default_value = "~/.config/solana/id.json")]
+    pub keypair: PathBuf,
+
+    /// Deployed SolDrip program id
+    #[arg(long, env = "SOLDRIP_PROGRAM_ID")]
+    pub program_id: Pubkey,
+
+    /// SolDrip state account; not needed for `init`
+    #[arg(long, env = "SOLDRIP_STATE")]
+    pub state: Option<Pubkey>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Create the mint, pools and state account, and mint the supply to the signer
+    Init {
+        /// Total supply in whole tokens
+        #[arg(long, default_value = "1000000000")]
+        total_supply: String,
+    },
+
+    /// Transfer tokens with tax from the signer's token account
+    Transfer {
+        /// Recipient wallet, or one of its SolDrip token accounts
+        destination: Pubkey,
+
+        /// Amount in tokens, e.g. 1000.5
+        amount: String,
+    },
+
+    /// Run a distribution round, or resume the open one
+    Distribute {
+        /// Token holders paid per DistributeDividends transaction
+        #[arg(long, default_value_t = 8)]
+        batch_size: usize,
+    },
+
+    /// Create holder records for token accounts that hold tokens but have none
+    InitHolders {
+        /// InitializeHolder instructions per transaction
+        #[arg(long, default_value_t = 8)]
+        batch_size: usize,
+    },
+
+    /// Claim the dividends accrued in the signer's holder record
+    Claim {
+        /// Token account to claim for; defaults to the signer's associated token account
+        #[arg(long)]
+        token_account: Option<Pubkey>,
+    },
+
+    /// Show the program state
+    Status,
+
+    /// Read or change the program configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+
+    /// Pause transfers, distributions and claims
+    Pause,
+
+    /// Resume transfers, distributions and claims
+    Unpause,
+
+    /// List token holders by balance
+    Holders {
+        /// Show only the largest holders
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print every configuration value, or a single one
+    Get {
+        /// Configuration key, e.g. sell_cooldown
+        key: Option<String>,
+    },
+
+    /// Change a single configuration value
+    Set {
+        /// Configuration key, e.g. sell_cooldown
+        key: String,
+
+        /// New value, in the format `config get` prints
+        value: String,
+    },
+}
+
+impl Cli {
+    // Expand a leading `~` so the default keypair path works as in the Solana CLI
+    pub fn keypair_path(&self) -> PathBuf {
+        match self.keypair.strip_prefix("~") {
+            Ok(rest) => match std::env::var_os("HOME") {
+                Some(home) => PathBuf::from(home).join(rest),
+                None => self.keypair.clone(),
+            },
+            Err(_) => self.keypair.clone(),
+        }
+    }
+}
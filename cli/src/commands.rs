@@ -0,0 +1,396 @@
+// Subcommand handlers
+
+use crate::{
+    cli::ConfigCommand,
+    context::Context,
+    error::CliError,
+    settings,
+    units::{format_sol, format_tokens, parse_tokens},
+};
+use borsh::BorshDeserialize;
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey, signature::Keypair, signer::Signer};
+use soldrip::{find_dividend_pool_address, find_distribution_history_address, DistributionHistory, HolderRecord, SolDripState};
+use spl_associated_token_account::{get_associated_token_address, instruction::create_associated_token_account_idempotent};
+use spl_token::state::Account as TokenAccount;
+use std::cmp::Reverse;
+
+// Create a new SolDrip deployment and mint the whole supply to the signer
+pub fn init(ctx: &Context, total_supply: &str) -> Result<(), CliError> {
+    let total_supply = parse_tokens(total_supply)?;
+    let authority = ctx.payer.pubkey();
+    let mint = Keypair::new();
+    let lp_pool = Keypair::new();
+    let state = Keypair::new();
+
+    let instruction = soldrip::initialize(
+        &ctx.program_id,
+        &authority,
+        &mint.pubkey(),
+        &lp_pool.pubkey(),
+        &state.pubkey(),
+        total_supply,
+    )?;
+    let signature = ctx.send(&[instruction], &[&mint, &lp_pool, &state])?;
+    println!("Initialized: {}", signature);
+
+    // The signer is the mint authority; mint the supply into its token account,
+    // and create the dividend pool's token account that collects the dividend tax
+    let (dividend_pool, _) = find_dividend_pool_address(&ctx.program_id, &state.pubkey());
+    let token_account = get_associated_token_address(&authority, &mint.pubkey());
+    let instructions = vec![
+        create_associated_token_account_idempotent(&authority, &dividend_pool, &mint.pubkey(), &spl_token::id()),
+        create_associated_token_account_idempotent(&authority, &authority, &mint.pubkey(), &spl_token::id()),
+        spl_token::instruction::mint_to(&spl_token::id(), &mint.pubkey(), &token_account, &authority, &[], total_supply)?,
+    ];
+    let signature = ctx.send(&instructions, &[])?;
+    println!("Minted {} tokens to {}: {}", format_tokens(total_supply), token_account, signature);
+
+    println!();
+    println!("State          {}", state.pubkey());
+    println!("Mint           {}", mint.pubkey());
+    println!("LP pool        {}", lp_pool.pubkey());
+    println!("Dividend pool  {}", dividend_pool);
+    println!();
+    println!("export SOLDRIP_STATE={}", state.pubkey());
+
+    Ok(())
+}
+
+// Transfer from the signer's associated token account. Wallet destinations
+// are paid into their associated token account, created if needed.
+pub fn transfer(ctx: &Context, destination: &Pubkey, amount: &str) -> Result<(), CliError> {
+    let amount = parse_tokens(amount)?;
+    let state = ctx.fetch_state()?;
+    let owner = ctx.payer.pubkey();
+    let source = get_associated_token_address(&owner, &state.mint);
+
+    let mut instructions = Vec::new();
+    let destination_token_account = match ctx.fetch_optional_account(destination)? {
+        Some(account) if account.owner == spl_token::id() => {
+            let token = TokenAccount::unpack(&account.data).map_err(|_| CliError::InvalidAccountData(destination.to_string()))?;
+            if token.mint != state.mint {
+                return Err(CliError::InvalidArgument(format!("{} is not a SolDrip token account", destination)));
+            }
+            *destination
+        },
+        _ => {
+            instructions.push(create_associated_token_account_idempotent(&owner, destination, &state.mint, &spl_token::id()));
+            get_associated_token_address(destination, &state.mint)
+        },
+    };
+
+    let reward_streams = ctx.fetch_reward_streams()?;
+    instructions.push(soldrip::transfer_with_tax(
+        &ctx.program_id,
+        &owner,
+        &ctx.state_address()?,
+        &state,
+        &source,
+        &destination_token_account,
+        &reward_streams,
+        amount,
+    )?);
+
+    let signature = ctx.send(&instructions, &[])?;
+    println!("Transferred {} tokens to {}: {}", format_tokens(amount), destination_token_account, signature);
+
+    Ok(())
+}
+
+// Pay every holder in one round: resume the open round with the holders it
+// has not paid, or start a new one if the program's distribution rules allow it
+pub fn distribute(ctx: &Context, batch_size: usize) -> Result<(), CliError> {
+    let state = ctx.fetch_state()?;
+    let state_address = ctx.state_address()?;
+
+    let (round_id, resuming) = match ctx.fetch_round()? {
+        Some(round) if round.is_active => {
+            println!("Resuming distribution round {}", round.round_id);
+            (round.round_id, true)
+        },
+        round => {
+            let clock = ctx.fetch_clock()?;
+            if let Err(reason) = state
+                .config
+                .distribution_due(state.pool_balance, state.last_distribution_timestamp, clock.unix_timestamp)
+            {
+                println!("Distribution not due ({} in pool): {}", format_sol(state.pool_balance), reason);
+                return Ok(());
+            }
+            (round.map_or(1, |round| round.round_id + 1), false)
+        },
+    };
+
+    // Only holder records earn dividends, each once per round. Records of
+    // closed token accounts are passed too, so their weight stops counting.
+    let records: Vec<HolderRecord> = ctx
+        .fetch_all_holder_records()?
+        .into_iter()
+        .filter(|record| record.paid_round != round_id)
+        .collect();
+    let addresses: Vec<Pubkey> = records.iter().map(|record| record.token_account).collect();
+    let balances = ctx.fetch_token_balances(&addresses)?;
+    let recipients: Vec<(Pubkey, Pubkey)> = records
+        .iter()
+        .zip(balances)
+        .filter_map(|(record, token)| record.distribution_recipient(token).map(|recipient| (record.token_account, recipient)))
+        .collect();
+
+    // The first DistributeDividends opens a new round; without one there is nothing to close
+    if recipients.is_empty() && !resuming {
+        println!("No holder records to pay, not starting a round");
+        return Ok(());
+    }
+    if !resuming {
+        println!("Starting distribution of {}", format_sol(state.pool_balance));
+    }
+
+    for (index, batch) in recipients.chunks(batch_size.max(1)).enumerate() {
+        let instruction =
+            soldrip::distribute_dividends(&ctx.program_id, &ctx.payer.pubkey(), &state.dividend_pool, &state_address, batch)?;
+        let signature = ctx.send(&[instruction], &[])?;
+        println!("Batch {} ({} holders): {}", index, batch.len(), signature);
+    }
+
+    let instruction = soldrip::complete_distribution_round(&ctx.program_id, &ctx.payer.pubkey(), &state_address)?;
+    let signature = ctx.send(&[instruction], &[])?;
+    println!("Distribution round completed: {}", signature);
+
+    Ok(())
+}
+
+// Create the missing holder records of funded token accounts, so holders
+// from before records were created on receipt are counted in the total weight.
+// Program and DEX token accounts earn no dividends and are skipped.
+pub fn init_holders(ctx: &Context, batch_size: usize) -> Result<(), CliError> {
+    let state = ctx.fetch_state()?;
+    let state_address = ctx.state_address()?;
+    let streams = ctx.fetch_reward_stream_accounts()?;
+    let reward_streams: Vec<Pubkey> = streams.iter().map(|(address, _)| *address).collect();
+
+    let holders = ctx.fetch_holders(&state.mint)?;
+    let addresses: Vec<Pubkey> = holders.iter().map(|(address, _)| *address).collect();
+    let records = ctx.fetch_holder_records(&addresses)?;
+    let missing: Vec<Pubkey> = holders
+        .iter()
+        .zip(records)
+        .filter(|((address, token), record)| {
+            record.is_none()
+                && token.amount > 0
+                && state.earns_dividends(address)
+                && !streams.iter().any(|(_, stream)| stream.holds(address))
+        })
+        .map(|((address, _), _)| *address)
+        .collect();
+
+    if missing.is_empty() {
+        println!("Every holder has a holder record");
+        return Ok(());
+    }
+
+    for (index, batch) in missing.chunks(batch_size.max(1)).enumerate() {
+        let instructions = batch
+            .iter()
+            .map(|token_account| {
+                soldrip::initialize_holder(&ctx.program_id, &ctx.payer.pubkey(), &state_address, token_account, &reward_streams)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let signature = ctx.send(&instructions, &[])?;
+        println!("Batch {} ({} holder records): {}", index, batch.len(), signature);
+    }
+    println!("Created {} holder records", missing.len());
+
+    Ok(())
+}
+
+// Claim accrued dividends as SOL to the holder's payout recipient
+pub fn claim(ctx: &Context, token_account: Option<Pubkey>) -> Result<(), CliError> {
+    let state = ctx.fetch_state()?;
+    let owner = ctx.payer.pubkey();
+    let token_account = token_account.unwrap_or_else(|| get_associated_token_address(&owner, &state.mint));
+
+    let record = ctx
+        .fetch_holder_record(&token_account)?
+        .ok_or_else(|| CliError::InvalidArgument(format!("{} has no holder record, nothing to claim", token_account)))?;
+    if record.accrued_lamports == 0 {
+        println!("Nothing to claim for {}", token_account);
+        return Ok(());
+    }
+    if record.compound {
+        return Err(CliError::InvalidArgument(
+            "Compounding claims swap through the swap adapter and are not supported by the CLI".to_string(),
+        ));
+    }
+
+    let recipient = record.payout_recipient(&owner);
+    let instruction = soldrip::claim_dividends(&ctx.program_id, &owner, &ctx.state_address()?, &token_account, &recipient, &[], 0)?;
+    let signature = ctx.send(&[instruction], &[])?;
+    println!("Claimed {} to {}: {}", format_sol(record.accrued_lamports), recipient, signature);
+
+    Ok(())
+}
+
+pub fn status(ctx: &Context) -> Result<(), CliError> {
+    let state_address = ctx.state_address()?;
+    let state = ctx.fetch_state()?;
+    let pool_lamports = ctx.client.get_balance(&state.dividend_pool)?;
+
+    println!("State                 {}", state_address);
+    println!("Authority             {}", state.authority);
+    println!("Mint                  {}", state.mint);
+    println!("Total supply          {}", format_tokens(state.total_supply));
+    println!("Burned                {}", format_tokens(state.total_burned));
+    println!("Circulating           {}", format_tokens(state.circulating_supply()));
+    println!("Paused                {}", yes_no(state.paused));
+    match state.trading_enabled {
+        true => println!("Trading               enabled at slot {}", state.launch_slot),
+        false => println!("Trading               not enabled"),
+    }
+    println!("Slippage protection   {}", yes_no(state.slippage_protection_active));
+    println!();
+    print_pool(&state, pool_lamports);
+    println!();
+
+    match ctx.fetch_round()? {
+        Some(round) if round.is_active => println!(
+            "Distribution round    {} in progress, {} of {} paid",
+            round.round_id,
+            format_sol(round.distributed_lamports),
+            format_sol(round.distributable_lamports),
+        ),
+        Some(round) => println!("Distribution round    {} (completed)", round.round_id),
+        None => println!("Distribution round    none yet"),
+    }
+    println!("Last distribution     {}", state.last_distribution_timestamp);
+    println!("Distribution epoch    {}", state.distribution_epoch);
+    println!("Total distributed     {}", format_sol(state.total_sol_distributed));
+
+    let (history_address, _) = find_distribution_history_address(&ctx.program_id, &state_address);
+    if let Some(account) = ctx.fetch_optional_account(&history_address)? {
+        let history = DistributionHistory::try_from_slice(&account.data)
+            .map_err(|_| CliError::InvalidAccountData(history_address.to_string()))?;
+        for round in history.rounds().take(5) {
+            println!(
+                "  epoch {:>6}  {}  {} holders  ended {}",
+                round.epoch,
+                format_sol(round.distributed_lamports),
+                round.holders_paid,
+                round.ended_at,
+            );
+        }
+    }
+    println!();
+
+    println!("Reward streams        {}", state.reward_stream_count);
+    match state.treasury_vault == Pubkey::default() {
+        true => println!("Treasury vault        not initialized"),
+        false => println!(
+            "Treasury vault        {} ({} withdrawn)",
+            state.treasury_vault,
+            format_tokens(state.total_treasury_withdrawn)
+        ),
+    }
+
+    Ok(())
+}
+
+fn print_pool(state: &SolDripState, pool_lamports: u64) {
+    println!("Dividend pool         {} ({})", state.dividend_pool, format_sol(pool_lamports));
+    println!("  distributable       {}", format_sol(state.pool_balance));
+    println!("  in current round    {}", format_sol(state.round_lamports));
+    println!("  unclaimed accruals  {}", format_sol(state.reserved_lamports));
+    println!("  donations           {}", format_sol(state.total_donations));
+}
+
+pub fn config(ctx: &Context, command: &ConfigCommand) -> Result<(), CliError> {
+    let state = ctx.fetch_state()?;
+
+    match command {
+        ConfigCommand::Get { key } => {
+            let entries = settings::entries(&state.config);
+            let width = entries.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+            let mut found = false;
+            for (name, value) in entries {
+                if key.as_deref().is_none_or(|key| key == name) {
+                    println!("{:width$}  {}", name, value, width = width);
+                    found = true;
+                }
+            }
+            if !found {
+                return Err(CliError::InvalidArgument(format!("Unknown configuration key: {}", key.as_deref().unwrap_or(""))));
+            }
+        },
+        ConfigCommand::Set { key, value } => {
+            let mut config = state.config;
+            settings::set(&mut config, key, value)?;
+            config
+                .validate()
+                .map_err(|err| CliError::InvalidArgument(format!("Rejected configuration: {}", err)))?;
+
+            let instruction = soldrip::update_config(&ctx.program_id, &ctx.payer.pubkey(), &ctx.state_address()?, config)?;
+            let signature = ctx.send(&[instruction], &[])?;
+            println!("Set {} = {}: {}", key, value, signature);
+        },
+    }
+
+    Ok(())
+}
+
+pub fn set_paused(ctx: &Context, paused: bool) -> Result<(), CliError> {
+    let instruction = soldrip::set_paused(&ctx.program_id, &ctx.payer.pubkey(), &ctx.state_address()?, paused)?;
+    let signature = ctx.send(&[instruction], &[])?;
+    println!("Program {}: {}", if paused { "paused" } else { "resumed" }, signature);
+
+    Ok(())
+}
+
+// Token holders by balance, with their holder record details
+pub fn holders(ctx: &Context, limit: Option<usize>) -> Result<(), CliError> {
+    let state = ctx.fetch_state()?;
+    let clock = ctx.fetch_clock()?;
+    let circulating = state.circulating_supply().max(1);
+
+    let holders = ctx.fetch_holders(&state.mint)?;
+    let addresses: Vec<Pubkey> = holders.iter().map(|(address, _)| *address).collect();
+    let records = ctx.fetch_holder_records(&addresses)?;
+
+    let mut rows: Vec<_> = holders.into_iter().zip(records).collect();
+    rows.sort_by_key(|((_, token), _)| Reverse(token.amount));
+    let total = rows.len();
+
+    println!(
+        "{:>4}  {:44}  {:44}  {:>20}  {:>7}  {:>9}  {:>14}",
+        "#", "Token account", "Owner", "Balance", "Share", "Coin age", "Accrued SOL"
+    );
+    for (rank, ((address, token), record)) in rows.iter().take(limit.unwrap_or(total)).enumerate() {
+        let share = token.amount as f64 * 100.0 / circulating as f64;
+        let (coin_age, accrued) = match record {
+            Some(record) => (
+                format!("{:.1}d", record.coin_age(clock.unix_timestamp) as f64 / 86_400.0),
+                (record.accrued_lamports as f64 / 1_000_000_000.0).to_string(),
+            ),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        println!(
+            "{:>4}  {:44}  {:44}  {:>20}  {:>6.3}%  {:>9}  {:>14}",
+            rank + 1,
+            address.to_string(),
+            token.owner.to_string(),
+            format_tokens(token.amount),
+            share,
+            coin_age,
+            accrued,
+        );
+    }
+    println!("{} holders", total);
+
+    Ok(())
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
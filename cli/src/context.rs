@@ -0,0 +1,196 @@
+// RPC handles and account fetching shared by all commands
+
+use crate::error::CliError;
+use borsh::BorshDeserialize;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    account::{from_account, Account},
+    clock::Clock,
+    instruction::Instruction,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    sysvar,
+    transaction::Transaction,
+};
+use soldrip::{find_distribution_round_address, find_holder_record_address, DistributionRound, HolderRecord, RewardStream, SolDripState};
+use spl_token::state::Account as TokenAccount;
+
+pub struct Context {
+    pub client: RpcClient,
+    pub payer: Keypair,
+    pub program_id: Pubkey,
+    pub state: Option<Pubkey>,
+}
+
+impl Context {
+    pub fn state_address(&self) -> Result<Pubkey, CliError> {
+        self.state.ok_or(CliError::MissingState)
+    }
+
+    // Sign with the payer and any extra signers, send and wait for confirmation
+    pub fn send(&self, instructions: &[Instruction], extra_signers: &[&Keypair]) -> Result<Signature, CliError> {
+        let mut signers = vec![&self.payer];
+        signers.extend_from_slice(extra_signers);
+
+        let blockhash = self.client.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(instructions, Some(&self.payer.pubkey()), &signers, blockhash);
+        Ok(self.client.send_and_confirm_transaction(&transaction)?)
+    }
+
+    pub fn fetch_optional_account(&self, address: &Pubkey) -> Result<Option<Account>, CliError> {
+        Ok(self.client.get_account_with_commitment(address, self.client.commitment())?.value)
+    }
+
+    pub fn fetch_account(&self, address: &Pubkey) -> Result<Account, CliError> {
+        self.fetch_optional_account(address)?
+            .ok_or_else(|| CliError::AccountNotFound(address.to_string()))
+    }
+
+    pub fn fetch_state(&self) -> Result<SolDripState, CliError> {
+        let address = self.state_address()?;
+        let account = self.fetch_account(&address)?;
+        SolDripState::try_from_slice(&account.data).map_err(|_| CliError::InvalidAccountData(address.to_string()))
+    }
+
+    // The round account is created by the first distribution, so it may not exist yet
+    pub fn fetch_round(&self) -> Result<Option<DistributionRound>, CliError> {
+        let (address, _) = find_distribution_round_address(&self.program_id, &self.state_address()?);
+        self.fetch_optional_account(&address)?
+            .map(|account| {
+                DistributionRound::try_from_slice(&account.data)
+                    .map_err(|_| CliError::InvalidAccountData(address.to_string()))
+            })
+            .transpose()
+    }
+
+    pub fn fetch_clock(&self) -> Result<Clock, CliError> {
+        let account = self.fetch_account(&sysvar::clock::id())?;
+        from_account::<Clock, _>(&account).ok_or_else(|| CliError::InvalidAccountData(sysvar::clock::id().to_string()))
+    }
+
+    pub fn fetch_holder_record(&self, token_account: &Pubkey) -> Result<Option<HolderRecord>, CliError> {
+        let (address, _) = find_holder_record_address(&self.program_id, &self.state_address()?, token_account);
+        self.fetch_optional_account(&address)?
+            .map(|account| {
+                HolderRecord::try_from_slice(&account.data).map_err(|_| CliError::InvalidAccountData(address.to_string()))
+            })
+            .transpose()
+    }
+
+    // Holder records of the given token accounts, `None` where none was created
+    pub fn fetch_holder_records(&self, token_accounts: &[Pubkey]) -> Result<Vec<Option<HolderRecord>>, CliError> {
+        let state = self.state_address()?;
+        let mut records = Vec::with_capacity(token_accounts.len());
+
+        // getMultipleAccounts accepts at most 100 addresses per call
+        for chunk in token_accounts.chunks(100) {
+            let addresses: Vec<Pubkey> = chunk
+                .iter()
+                .map(|token_account| find_holder_record_address(&self.program_id, &state, token_account).0)
+                .collect();
+            for account in self.client.get_multiple_accounts(&addresses)? {
+                records.push(account.and_then(|account| HolderRecord::try_from_slice(&account.data).ok()));
+            }
+        }
+
+        Ok(records)
+    }
+
+    // Every holder record of the state, sorted by token account
+    pub fn fetch_all_holder_records(&self) -> Result<Vec<HolderRecord>, CliError> {
+        let state = self.state_address()?;
+        let filters = vec![
+            RpcFilterType::DataSize(HolderRecord::LEN as u64),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, state.as_ref())),
+        ];
+
+        let mut records: Vec<HolderRecord> = self
+            .program_accounts(&self.program_id, filters)?
+            .into_iter()
+            .filter_map(|(_, account)| HolderRecord::try_from_slice(&account.data).ok())
+            .collect();
+        records.sort_by_key(|record| record.token_account);
+
+        Ok(records)
+    }
+
+    // Owner and balance of the given token accounts, `None` where one was closed
+    pub fn fetch_token_balances(&self, token_accounts: &[Pubkey]) -> Result<Vec<Option<(Pubkey, u64)>>, CliError> {
+        let mut balances = Vec::with_capacity(token_accounts.len());
+
+        // getMultipleAccounts accepts at most 100 addresses per call
+        for chunk in token_accounts.chunks(100) {
+            for account in self.client.get_multiple_accounts(chunk)? {
+                let token = account.and_then(|account| TokenAccount::unpack(&account.data).ok());
+                balances.push(token.map(|token| (token.owner, token.amount)));
+            }
+        }
+
+        Ok(balances)
+    }
+
+    // Every non-empty token account of the SolDrip mint, sorted by address
+    pub fn fetch_holders(&self, mint: &Pubkey) -> Result<Vec<(Pubkey, TokenAccount)>, CliError> {
+        let filters = vec![
+            RpcFilterType::DataSize(TokenAccount::LEN as u64),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, mint.as_ref())),
+        ];
+        let accounts = self.program_accounts(&spl_token::id(), filters)?;
+
+        let mut holders: Vec<(Pubkey, TokenAccount)> = accounts
+            .into_iter()
+            .filter_map(|(address, account)| {
+                TokenAccount::unpack(&account.data)
+                    .ok()
+                    .filter(|token| token.amount > 0)
+                    .map(|token| (address, token))
+            })
+            .collect();
+        holders.sort_by_key(|(address, _)| *address);
+
+        Ok(holders)
+    }
+
+    // Addresses of the state's reward streams; TransferWithTax needs all of them
+    pub fn fetch_reward_streams(&self) -> Result<Vec<Pubkey>, CliError> {
+        Ok(self.fetch_reward_stream_accounts()?.into_iter().map(|(address, _)| address).collect())
+    }
+
+    // The state's reward streams, with their addresses
+    pub fn fetch_reward_stream_accounts(&self) -> Result<Vec<(Pubkey, RewardStream)>, CliError> {
+        let state = self.state_address()?;
+        let filters = vec![
+            RpcFilterType::DataSize(RewardStream::LEN as u64),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, state.as_ref())),
+        ];
+
+        self.program_accounts(&self.program_id, filters)?
+            .into_iter()
+            .map(|(address, account)| {
+                RewardStream::try_from_slice(&account.data)
+                    .map(|stream| (address, stream))
+                    .map_err(|_| CliError::InvalidAccountData(address.to_string()))
+            })
+            .collect()
+    }
+
+    fn program_accounts(&self, program_id: &Pubkey, filters: Vec<RpcFilterType>) -> Result<Vec<(Pubkey, Account)>, CliError> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+
+        Ok(self.client.get_program_accounts_with_config(program_id, config)?)
+    }
+}
@@ -0,0 +1,34 @@
+// CLI error type
+
+use solana_client::client_error::ClientError;
+use solana_sdk::program_error::ProgramError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CliError {
+    #[error("RPC error: {0}")]
+    Rpc(Box<ClientError>),
+
+    #[error("Failed to read keypair {0}")]
+    Keypair(String),
+
+    #[error("Account {0} not found")]
+    AccountNotFound(String),
+
+    #[error("Failed to decode account {0}")]
+    InvalidAccountData(String),
+
+    #[error("Failed to build instruction: {0}")]
+    Instruction(#[from] ProgramError),
+
+    #[error("{0}")]
+    InvalidArgument(String),
+
+    #[error("--state (or SOLDRIP_STATE) is required for this command")]
+    MissingState,
+}
+
+impl From<ClientError> for CliError {
+    fn from(e: ClientError) -> Self {
+        CliError::Rpc(Box::new(e))
+    }
+}
@@ -0,0 +1,48 @@
+// SolDrip CLI
+// Operator tool for deploying, inspecting and administering a SolDrip token
+
+mod cli;
+mod commands;
+mod context;
+mod error;
+mod settings;
+mod units;
+
+use clap::Parser;
+use cli::{Cli, Command};
+use context::Context;
+use error::CliError;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::read_keypair_file};
+use std::process;
+
+fn run(cli: Cli) -> Result<(), CliError> {
+    let keypair_path = cli.keypair_path();
+    let payer = read_keypair_file(&keypair_path).map_err(|_| CliError::Keypair(keypair_path.display().to_string()))?;
+    let ctx = Context {
+        client: RpcClient::new_with_commitment(cli.rpc_url.clone(), CommitmentConfig::confirmed()),
+        payer,
+        program_id: cli.program_id,
+        state: cli.state,
+    };
+
+    match &cli.command {
+        Command::Init { total_supply } => commands::init(&ctx, total_supply),
+        Command::Transfer { destination, amount } => commands::transfer(&ctx, destination, amount),
+        Command::Distribute { batch_size } => commands::distribute(&ctx, *batch_size),
+        Command::InitHolders { batch_size } => commands::init_holders(&ctx, *batch_size),
+        Command::Claim { token_account } => commands::claim(&ctx, *token_account),
+        Command::Status => commands::status(&ctx),
+        Command::Config { command } => commands::config(&ctx, command),
+        Command::Pause => commands::set_paused(&ctx, true),
+        Command::Unpause => commands::set_paused(&ctx, false),
+        Command::Holders { limit } => commands::holders(&ctx, *limit),
+    }
+}
+
+fn main() {
+    if let Err(err) = run(Cli::parse()) {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    }
+}
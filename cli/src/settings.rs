@@ -0,0 +1,149 @@
+// `config get` / `config set`: SolDripConfig fields as text key/value pairs.
+// Tier lists are comma-separated, with each tier's values joined by `/`:
+//   tax_tiers        min_amount_bps/lp_bps/dividend_bps/burn_bps/treasury_bps,...
+//   coin_age_tiers   min_age/multiplier_bps,...
+
+use crate::error::CliError;
+use soldrip::{CoinAgeCurve, CoinAgeTier, SolDripConfig, TaxTier, MAX_COIN_AGE_TIERS, MAX_TAX_TIERS};
+use std::str::FromStr;
+
+// Every configuration value, in declaration order
+pub fn entries(config: &SolDripConfig) -> Vec<(&'static str, String)> {
+    vec![
+        ("swap_program", config.swap_program.to_string()),
+        ("max_compound_holding_bps", config.max_compound_holding_bps.to_string()),
+        ("min_distribution_interval", config.min_distribution_interval.to_string()),
+        ("force_distribution_after", config.force_distribution_after.to_string()),
+        ("market_token_account", config.market_token_account.to_string()),
+        ("reject_buys_before_trading", config.reject_buys_before_trading.to_string()),
+        ("launch_tax_bps", config.launch_tax_bps.to_string()),
+        ("launch_tax_slots", config.launch_tax_slots.to_string()),
+        ("max_transaction_amount", config.max_transaction_amount.to_string()),
+        ("sell_cooldown", config.sell_cooldown.to_string()),
+        ("tax_tiers", format_tax_tiers(config)),
+        ("treasury_authority", config.treasury_authority.to_string()),
+        ("treasury_withdraw_limit", config.treasury_withdraw_limit.to_string()),
+        ("treasury_withdraw_period", config.treasury_withdraw_period.to_string()),
+        ("coin_age_curve", format_curve(config.coin_age_curve).to_string()),
+        ("coin_age_max_multiplier_bps", config.coin_age_max_multiplier_bps.to_string()),
+        ("coin_age_ramp", config.coin_age_ramp.to_string()),
+        ("coin_age_tiers", format_coin_age_tiers(config)),
+        ("snapshot_eligibility", config.snapshot_eligibility.to_string()),
+        ("merkle_publisher", config.merkle_publisher.to_string()),
+        ("stream_converter", config.stream_converter.to_string()),
+    ]
+}
+
+// Set one value; the caller validates the resulting configuration
+pub fn set(config: &mut SolDripConfig, key: &str, value: &str) -> Result<(), CliError> {
+    match key {
+        "swap_program" => config.swap_program = parse(key, value)?,
+        "max_compound_holding_bps" => config.max_compound_holding_bps = parse(key, value)?,
+        "min_distribution_interval" => config.min_distribution_interval = parse(key, value)?,
+        "force_distribution_after" => config.force_distribution_after = parse(key, value)?,
+        "market_token_account" => config.market_token_account = parse(key, value)?,
+        "reject_buys_before_trading" => config.reject_buys_before_trading = parse(key, value)?,
+        "launch_tax_bps" => config.launch_tax_bps = parse(key, value)?,
+        "launch_tax_slots" => config.launch_tax_slots = parse(key, value)?,
+        "max_transaction_amount" => config.max_transaction_amount = parse(key, value)?,
+        "sell_cooldown" => config.sell_cooldown = parse(key, value)?,
+        "tax_tiers" => {
+            let tiers = parse_tiers(key, value, MAX_TAX_TIERS, 5)?;
+            config.tax_tiers = [TaxTier::default(); MAX_TAX_TIERS];
+            for (tier, values) in config.tax_tiers.iter_mut().zip(&tiers) {
+                *tier = TaxTier {
+                    min_amount_bps: bps(key, values[0])?,
+                    lp_bps: bps(key, values[1])?,
+                    dividend_bps: bps(key, values[2])?,
+                    burn_bps: bps(key, values[3])?,
+                    treasury_bps: bps(key, values[4])?,
+                };
+            }
+            config.tax_tier_count = tiers.len() as u8;
+        },
+        "treasury_authority" => config.treasury_authority = parse(key, value)?,
+        "treasury_withdraw_limit" => config.treasury_withdraw_limit = parse(key, value)?,
+        "treasury_withdraw_period" => config.treasury_withdraw_period = parse(key, value)?,
+        "coin_age_curve" => {
+            config.coin_age_curve = match value {
+                "step-bonus" => CoinAgeCurve::StepBonus,
+                "linear" => CoinAgeCurve::Linear,
+                "tiered" => CoinAgeCurve::Tiered,
+                _ => return Err(invalid(key, value)),
+            }
+        },
+        "coin_age_max_multiplier_bps" => config.coin_age_max_multiplier_bps = parse(key, value)?,
+        "coin_age_ramp" => config.coin_age_ramp = parse(key, value)?,
+        "coin_age_tiers" => {
+            let tiers = parse_tiers(key, value, MAX_COIN_AGE_TIERS, 2)?;
+            config.coin_age_tiers = [CoinAgeTier::default(); MAX_COIN_AGE_TIERS];
+            for (tier, values) in config.coin_age_tiers.iter_mut().zip(&tiers) {
+                *tier = CoinAgeTier {
+                    min_age: values[0].try_into().map_err(|_| invalid(key, value))?,
+                    multiplier_bps: values[1],
+                };
+            }
+            config.coin_age_tier_count = tiers.len() as u8;
+        },
+        "snapshot_eligibility" => config.snapshot_eligibility = parse(key, value)?,
+        "merkle_publisher" => config.merkle_publisher = parse(key, value)?,
+        "stream_converter" => config.stream_converter = parse(key, value)?,
+        _ => return Err(CliError::InvalidArgument(format!("Unknown configuration key: {}", key))),
+    }
+
+    Ok(())
+}
+
+fn format_tax_tiers(config: &SolDripConfig) -> String {
+    config.tax_tiers[..config.tax_tier_count as usize]
+        .iter()
+        .map(|tier| {
+            format!(
+                "{}/{}/{}/{}/{}",
+                tier.min_amount_bps, tier.lp_bps, tier.dividend_bps, tier.burn_bps, tier.treasury_bps
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn format_coin_age_tiers(config: &SolDripConfig) -> String {
+    config.coin_age_tiers[..config.coin_age_tier_count as usize]
+        .iter()
+        .map(|tier| format!("{}/{}", tier.min_age, tier.multiplier_bps))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn format_curve(curve: CoinAgeCurve) -> &'static str {
+    match curve {
+        CoinAgeCurve::StepBonus => "step-bonus",
+        CoinAgeCurve::Linear => "linear",
+        CoinAgeCurve::Tiered => "tiered",
+    }
+}
+
+fn parse<T: FromStr>(key: &str, value: &str) -> Result<T, CliError> {
+    value.parse().map_err(|_| invalid(key, value))
+}
+
+fn bps(key: &str, value: u64) -> Result<u16, CliError> {
+    value.try_into().map_err(|_| invalid(key, &value.to_string()))
+}
+
+// Split "a/b/c,d/e/f" into tiers of `width` numbers each
+fn parse_tiers(key: &str, value: &str, max_tiers: usize, width: usize) -> Result<Vec<Vec<u64>>, CliError> {
+    let tiers = value
+        .split(',')
+        .map(|tier| tier.split('/').map(|part| parse::<u64>(key, part.trim())).collect::<Result<Vec<_>, _>>())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if tiers.is_empty() || tiers.len() > max_tiers || tiers.iter().any(|tier| tier.len() != width) {
+        return Err(invalid(key, value));
+    }
+    Ok(tiers)
+}
+
+fn invalid(key: &str, value: &str) -> CliError {
+    CliError::InvalidArgument(format!("Invalid value for {}: {}", key, value))
+}
@@ -0,0 +1,81 @@
+// Token and SOL amount parsing and formatting
+
+use crate::error::CliError;
+
+// SolDrip mints use 9 decimals, like SOL
+pub const TOKEN_DECIMALS: u32 = 9;
+const UNITS_PER_TOKEN: u64 = 10u64.pow(TOKEN_DECIMALS);
+
+// Parse a decimal token amount such as "1000.5" into base units, exactly
+pub fn parse_tokens(amount: &str) -> Result<u64, CliError> {
+    let invalid = || CliError::InvalidArgument(format!("Invalid token amount: {}", amount));
+
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    if (whole.is_empty() && fraction.is_empty()) || fraction.len() > TOKEN_DECIMALS as usize {
+        return Err(invalid());
+    }
+    let digits = |part: &str| part.is_empty() || part.chars().all(|c| c.is_ascii_digit());
+    if !digits(whole) || !digits(fraction) {
+        return Err(invalid());
+    }
+
+    let whole: u64 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| invalid())? };
+    let fraction: u64 = format!("{:0<width$}", fraction, width = TOKEN_DECIMALS as usize)
+        .parse()
+        .map_err(|_| invalid())?;
+
+    whole
+        .checked_mul(UNITS_PER_TOKEN)
+        .and_then(|units| units.checked_add(fraction))
+        .ok_or_else(invalid)
+}
+
+// Format base units as tokens, trimming trailing zeros
+pub fn format_tokens(units: u64) -> String {
+    let fraction = units % UNITS_PER_TOKEN;
+    if fraction == 0 {
+        return (units / UNITS_PER_TOKEN).to_string();
+    }
+    let fraction = format!("{:0>width$}", fraction, width = TOKEN_DECIMALS as usize);
+    format!("{}.{}", units / UNITS_PER_TOKEN, fraction.trim_end_matches('0'))
+}
+
+pub fn format_sol(lamports: u64) -> String {
+    format!("{} SOL", lamports as f64 / 1_000_000_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_decimal_amounts() {
+        assert_eq!(parse_tokens("1000.5").unwrap(), 1_000_500_000_000);
+        assert_eq!(parse_tokens(".5").unwrap(), 500_000_000);
+        assert_eq!(parse_tokens("1.").unwrap(), 1_000_000_000);
+        assert_eq!(parse_tokens("0.000000001").unwrap(), 1);
+        assert_eq!(parse_tokens("42").unwrap(), 42_000_000_000);
+    }
+
+    #[test]
+    fn rejects_malformed_amounts() {
+        for amount in ["", ".", "1.0000000001", "1.5.0", "-1", "1e9", " 1", "abc"] {
+            assert!(parse_tokens(amount).is_err(), "{:?} should be rejected", amount);
+        }
+    }
+
+    #[test]
+    fn rejects_amounts_that_overflow() {
+        assert_eq!(parse_tokens("18446744073.709551615").unwrap(), u64::MAX);
+        assert!(parse_tokens("18446744073.709551616").is_err());
+        assert!(parse_tokens("18446744074").is_err());
+        assert!(parse_tokens("99999999999999999999999").is_err());
+    }
+
+    #[test]
+    fn formats_round_trip() {
+        for amount in ["1000.5", "0.000000001", "42", "18446744073.709551615"] {
+            assert_eq!(format_tokens(parse_tokens(amount).unwrap()), amount);
+        }
+    }
+}
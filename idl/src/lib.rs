@@ -0,0 +1,367 @@
+// SolDrip IDL generator
+// Builds the program's IDL from the shank annotations in its source:
+// `ShankInstruction` variants with their `#[account]` attributes,
+// `ShankAccount` structs, borsh custom types and the thiserror error enum
+
+mod idl;
+
+pub use idl::*;
+
+use anyhow::{anyhow, bail, Context, Result};
+use shank_macro_impl::{
+    account::extract_account_structs,
+    custom_type::DetectCustomTypeConfig,
+    error::extract_this_errors,
+    instruction::{Instruction, InstructionVariantFields},
+    krate::CrateContext,
+    parsed_enum::ParsedEnum,
+    parsed_struct::ParsedStruct,
+    types::{Composite, Primitive, RustType, TypeKind, Value},
+};
+use std::{collections::HashMap, convert::TryFrom, fs, path::Path};
+use syn::{visit_mut::VisitMut, Attribute, Expr, Item, ItemEnum, ItemStruct, Lit, LitStr, Meta, Type};
+
+// Generate the IDL of the program crate at `program_dir`
+pub fn generate(program_dir: &Path) -> Result<Idl> {
+    let (name, version) = read_manifest(&program_dir.join("Cargo.toml"))?;
+    let crate_ctx = CrateContext::parse(program_dir.join("src").join("lib.rs"))
+        .map_err(|err| anyhow!("Failed to parse {}: {}", program_dir.display(), err))?;
+
+    // shank only resolves array lengths given as literals, and no type aliases
+    let mut items = crate_ctx.all_items_vec();
+    let mut inliner = ArrayLenInliner::new(&items);
+    for item in &mut items {
+        inliner.visit_item_mut(item);
+    }
+
+    let enums: Vec<&ItemEnum> = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Enum(item_enum) => Some(item_enum),
+            _ => None,
+        })
+        .collect();
+    let structs: Vec<&ItemStruct> = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Struct(item_struct) => Some(item_struct),
+            _ => None,
+        })
+        .collect();
+
+    Ok(Idl {
+        version,
+        name,
+        instructions: instructions(&enums)?,
+        accounts: accounts(&structs)?,
+        types: custom_types(&items)?,
+        errors: errors(&enums)?,
+        metadata: IdlMetadata {
+            origin: "shank".to_string(),
+            address: program_address(&items)?,
+        },
+    })
+}
+
+// Package name and version from the program's Cargo.toml
+fn read_manifest(path: &Path) -> Result<(String, String)> {
+    let manifest = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let value = |key: &str| {
+        manifest
+            .lines()
+            .find_map(|line| {
+                let (k, v) = line.split_once('=')?;
+                (k.trim() == key).then(|| v.trim().trim_matches('"').to_string())
+            })
+            .ok_or_else(|| anyhow!("{} has no package {}", path.display(), key))
+    };
+
+    Ok((value("name")?, value("version")?))
+}
+
+// The program id passed to `declare_id!`
+fn program_address(items: &[Item]) -> Result<String> {
+    items
+        .iter()
+        .find_map(|item| match item {
+            Item::Macro(item_macro) if item_macro.mac.path.segments.last()?.ident == "declare_id" => {
+                item_macro.mac.parse_body::<LitStr>().ok()
+            },
+            _ => None,
+        })
+        .map(|address| address.value())
+        .ok_or_else(|| anyhow!("No declare_id! found"))
+}
+
+fn instructions(enums: &[&ItemEnum]) -> Result<Vec<IdlInstruction>> {
+    let mut instructions = Vec::new();
+
+    for item_enum in enums {
+        let instruction = match Instruction::try_from_item_enum(item_enum, false)? {
+            Some(instruction) => instruction,
+            None => continue,
+        };
+
+        for (variant, item_variant) in instruction.variants.iter().zip(&item_enum.variants) {
+            let mut accounts: Vec<_> = variant.accounts.iter().collect();
+            accounts.sort_by_key(|account| account.index);
+            for (position, account) in accounts.iter().enumerate() {
+                if account.index.is_some_and(|index| index as usize != position) {
+                    bail!("{}: account {} is out of order", variant.ident, account.name);
+                }
+            }
+
+            let args = match &variant.field_tys {
+                InstructionVariantFields::Named(fields) => fields
+                    .iter()
+                    .zip(&item_variant.fields)
+                    .map(|((name, ty), field)| {
+                        Ok(IdlField {
+                            name: camel_case(name),
+                            docs: docs(&field.attrs),
+                            ty: idl_type(ty)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+                InstructionVariantFields::Unnamed(tys) => tys
+                    .iter()
+                    .enumerate()
+                    .map(|(index, ty)| {
+                        Ok(IdlField {
+                            name: format!("arg{}", index),
+                            docs: Vec::new(),
+                            ty: idl_type(ty)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            };
+
+            instructions.push(IdlInstruction {
+                name: variant.ident.to_string(),
+                docs: docs(&item_variant.attrs),
+                accounts: accounts
+                    .into_iter()
+                    .map(|account| IdlAccount {
+                        name: camel_case(&account.name),
+                        is_mut: account.writable,
+                        is_signer: account.signer,
+                        optional: account.optional,
+                        desc: account.desc.clone(),
+                    })
+                    .collect(),
+                args,
+                discriminant: IdlDiscriminant {
+                    ty: IdlType::U8,
+                    value: variant.discriminant,
+                },
+            });
+        }
+    }
+
+    if instructions.is_empty() {
+        bail!("No ShankInstruction enum found");
+    }
+    Ok(instructions)
+}
+
+fn accounts(structs: &[&ItemStruct]) -> Result<Vec<IdlTypeDef>> {
+    extract_account_structs(structs.iter().copied())?
+        .iter()
+        .map(|parsed| {
+            let item = structs
+                .iter()
+                .find(|item| item.ident == parsed.ident)
+                .ok_or_else(|| anyhow!("Account {} not found", parsed.ident))?;
+            struct_def(parsed, item)
+        })
+        .collect()
+}
+
+// Borsh structs and enums used by instructions, accounts and return data
+fn custom_types(items: &[Item]) -> Result<Vec<IdlTypeDef>> {
+    let config = DetectCustomTypeConfig::default();
+    let mut types = Vec::new();
+
+    for item in items {
+        match item {
+            Item::Struct(item) if config.are_custom_type_attrs(&item.attrs) => {
+                let parsed = ParsedStruct::try_from(item)?;
+                types.push(struct_def(&parsed, item)?);
+            },
+            Item::Enum(item) if config.are_custom_type_attrs(&item.attrs) => {
+                let parsed = ParsedEnum::try_from(item)?;
+                if let Some(variant) = parsed.variants.iter().find(|variant| !variant.fields.is_empty()) {
+                    bail!("{}::{}: only unit enum variants are supported", parsed.ident, variant.ident);
+                }
+                types.push(IdlTypeDef {
+                    name: parsed.ident.to_string(),
+                    docs: docs(&item.attrs),
+                    ty: IdlTypeDefTy::Enum {
+                        variants: parsed
+                            .variants
+                            .iter()
+                            .map(|variant| IdlEnumVariant { name: variant.ident.to_string() })
+                            .collect(),
+                    },
+                });
+            },
+            _ => {},
+        }
+    }
+
+    Ok(types)
+}
+
+fn struct_def(parsed: &ParsedStruct, item: &ItemStruct) -> Result<IdlTypeDef> {
+    let fields = parsed
+        .fields
+        .iter()
+        .zip(&item.fields)
+        .map(|(field, item_field)| {
+            Ok(IdlField {
+                name: camel_case(&field.ident.to_string()),
+                docs: docs(&item_field.attrs),
+                ty: idl_type(&field.rust_type)?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(IdlTypeDef {
+        name: parsed.ident.to_string(),
+        docs: docs(&item.attrs),
+        ty: IdlTypeDefTy::Struct { fields },
+    })
+}
+
+fn errors(enums: &[&ItemEnum]) -> Result<Vec<IdlError>> {
+    Ok(extract_this_errors(enums.iter().copied())?
+        .into_iter()
+        .map(|error| IdlError {
+            code: error.code,
+            name: error.name,
+            msg: error.desc,
+        })
+        .collect())
+}
+
+// Map a Rust field type to its borsh IDL type
+fn idl_type(ty: &RustType) -> Result<IdlType> {
+    let single = |inners: &[RustType]| match inners {
+        [inner] => idl_type(inner),
+        _ => bail!("{} needs exactly one type parameter", ty.ident),
+    };
+
+    Ok(match &ty.kind {
+        TypeKind::Primitive(primitive) => match primitive {
+            Primitive::Bool => IdlType::Bool,
+            Primitive::U8 => IdlType::U8,
+            Primitive::I8 => IdlType::I8,
+            Primitive::U16 => IdlType::U16,
+            Primitive::I16 => IdlType::I16,
+            Primitive::U32 => IdlType::U32,
+            Primitive::I32 => IdlType::I32,
+            // borsh encodes usize as u64
+            Primitive::U64 | Primitive::USize => IdlType::U64,
+            Primitive::I64 => IdlType::I64,
+            Primitive::U128 => IdlType::U128,
+            Primitive::I128 => IdlType::I128,
+        },
+        TypeKind::Value(Value::String | Value::Str | Value::CString) => IdlType::String,
+        TypeKind::Value(Value::Custom(name)) if name == "Pubkey" => IdlType::PublicKey,
+        TypeKind::Value(Value::Custom(name)) => IdlType::Defined(name.clone()),
+        TypeKind::Composite(Composite::Vec, inners) => IdlType::Vec(Box::new(single(inners)?)),
+        TypeKind::Composite(Composite::Option, inners) => IdlType::Option(Box::new(single(inners)?)),
+        TypeKind::Composite(Composite::Array(len), inners) => IdlType::Array(Box::new(single(inners)?), *len),
+        TypeKind::Composite(Composite::Tuple, inners) => {
+            IdlType::Tuple(inners.iter().map(idl_type).collect::<Result<_>>()?)
+        },
+        // Boxing does not change the borsh encoding
+        TypeKind::Composite(Composite::Custom(name), inners) if name == "Box" => single(inners)?,
+        _ => bail!("Unsupported type {}", ty.ident),
+    })
+}
+
+// `///` doc lines of an item
+fn docs(attrs: &[Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(meta)) => match meta.lit {
+                Lit::Str(doc) => Some(doc.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+fn camel_case(name: &str) -> String {
+    let mut camel = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            camel.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+// Replaces array lengths given as integer constants, e.g.
+// `[TaxTier; MAX_TAX_TIERS]`, with their literal values, and type
+// aliases with the types they name
+struct ArrayLenInliner {
+    consts: HashMap<String, Expr>,
+    aliases: HashMap<String, Type>,
+}
+
+impl ArrayLenInliner {
+    fn new(items: &[Item]) -> Self {
+        let consts = items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Const(item_const) => match item_const.expr.as_ref() {
+                    expr @ Expr::Lit(syn::ExprLit { lit: Lit::Int(_), .. }) => {
+                        Some((item_const.ident.to_string(), expr.clone()))
+                    },
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        let aliases = items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Type(item_type) => Some((item_type.ident.to_string(), (*item_type.ty).clone())),
+                _ => None,
+            })
+            .collect();
+
+        ArrayLenInliner { consts, aliases }
+    }
+}
+
+impl VisitMut for ArrayLenInliner {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let Type::Path(path) = ty {
+            if let Some(alias) = path.path.get_ident().and_then(|ident| self.aliases.get(&ident.to_string())) {
+                *ty = alias.clone();
+            }
+        }
+        syn::visit_mut::visit_type_mut(self, ty);
+    }
+
+    fn visit_type_array_mut(&mut self, array: &mut syn::TypeArray) {
+        if let Expr::Path(path) = &array.len {
+            if let Some(value) = path.path.get_ident().and_then(|ident| self.consts.get(&ident.to_string())) {
+                array.len = value.clone();
+            }
+        }
+        syn::visit_mut::visit_type_array_mut(self, array);
+    }
+}
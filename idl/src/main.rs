@@ -0,0 +1,54 @@
+// SolDrip IDL generator
+// Writes the program IDL as JSON, or checks that the committed copy is current
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use std::{fs, path::PathBuf, process};
+
+#[derive(Parser, Debug)]
+#[command(name = "soldrip-idl", about = "Generates the SolDrip IDL from the program's shank annotations")]
+struct Args {
+    /// Program crate to read the annotations from
+    #[arg(long, default_value = "programs/soldrip")]
+    program_dir: PathBuf,
+
+    /// IDL JSON file to write
+    #[arg(long, default_value = "idl/soldrip.json")]
+    out: PathBuf,
+
+    /// Fail if the IDL file is out of date instead of writing it
+    #[arg(long)]
+    check: bool,
+}
+
+fn run(args: Args) -> Result<()> {
+    let idl = soldrip_idl::generate(&args.program_dir)?;
+    let json = serde_json::to_string_pretty(&idl)? + "\n";
+
+    if args.check {
+        let current = fs::read_to_string(&args.out).unwrap_or_default();
+        if current != json {
+            bail!("{} is out of date, run soldrip-idl to regenerate it", args.out.display());
+        }
+        return Ok(());
+    }
+
+    fs::write(&args.out, json).with_context(|| format!("Failed to write {}", args.out.display()))?;
+    println!(
+        "Wrote {} ({} instructions, {} accounts, {} types, {} errors)",
+        args.out.display(),
+        idl.instructions.len(),
+        idl.accounts.len(),
+        idl.types.len(),
+        idl.errors.len(),
+    );
+
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run(Args::parse()) {
+        eprintln!("{:#}", err);
+        process::exit(1);
+    }
+}
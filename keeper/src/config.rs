@@ -0,0 +1,101 @@
+// Keeper configuration, read from command-line flags or environment variables
+
+use clap::Parser;
+use solana_sdk::pubkey::Pubkey;
+use std::{path::PathBuf, str::FromStr, time::Duration};
+
+#[derive(Parser, Debug, Clone)]
+#[command(name = "soldrip-keeper", about = "Cranks SolDrip dividend distributions and housekeeping")]
+pub struct Config {
+    /// RPC endpoint, e.g. http://127.0.0.1:8899 for solana-test-validator
+    #[arg(long, env = "SOLDRIP_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    pub rpc_url: String,
+
+    /// Keypair that signs and pays for crank transactions
+    #[arg(long, env = "SOLDRIP_KEYPAIR", default_value = "~/.config/solana/id.json")]
+    pub keypair: PathBuf,
+
+    /// Deployed SolDrip program id
+    #[arg(long, env = "SOLDRIP_PROGRAM_ID")]
+    pub program_id: Pubkey,
+
+    /// SolDrip state account
+    #[arg(long, env = "SOLDRIP_STATE")]
+    pub state: Pubkey,
+
+    /// Seconds between polls of the dividend pool
+    #[arg(long, default_value_t = 30)]
+    pub poll_interval_secs: u64,
+
+    /// Token holders paid per DistributeDividends transaction (three accounts each)
+    #[arg(long, default_value_t = 8)]
+    pub batch_size: usize,
+
+    /// Attempts per RPC call or transaction before giving up
+    #[arg(long, default_value_t = 5)]
+    pub max_retries: u32,
+
+    /// Initial retry backoff in milliseconds, doubled after every failed attempt
+    #[arg(long, default_value_t = 500)]
+    pub backoff_ms: u64,
+
+    /// Upper bound for the retry backoff in milliseconds
+    #[arg(long, default_value_t = 30_000)]
+    pub max_backoff_ms: u64,
+
+    /// Tolerated shortfall of a reward stream conversion below its simulated
+    /// output, in basis points
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u16).range(0..=10_000))]
+    pub convert_slippage_bps: u16,
+
+    /// Accounts forwarded to the swap adapter when converting a reward
+    /// stream's tax, as REWARD_MINT=ACCOUNT[,ACCOUNT...]; repeat per stream
+    #[arg(long = "swap-accounts", value_parser = parse_swap_accounts)]
+    pub swap_accounts: Vec<(Pubkey, Vec<Pubkey>)>,
+
+    /// Build and simulate transactions without submitting them
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Run a single crank pass and exit
+    #[arg(long)]
+    pub once: bool,
+}
+
+impl Config {
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.poll_interval_secs)
+    }
+
+    // Swap adapter accounts configured for a reward stream's mint
+    pub fn swap_accounts_for(&self, reward_mint: &Pubkey) -> &[Pubkey] {
+        self.swap_accounts
+            .iter()
+            .find(|(mint, _)| mint == reward_mint)
+            .map_or(&[], |(_, accounts)| accounts)
+    }
+
+    // Expand a leading `~` so the default keypair path works as in the Solana CLI
+    pub fn keypair_path(&self) -> PathBuf {
+        match self.keypair.strip_prefix("~") {
+            Ok(rest) => match std::env::var_os("HOME") {
+                Some(home) => PathBuf::from(home).join(rest),
+                None => self.keypair.clone(),
+            },
+            Err(_) => self.keypair.clone(),
+        }
+    }
+}
+
+fn parse_swap_accounts(value: &str) -> Result<(Pubkey, Vec<Pubkey>), String> {
+    let (mint, accounts) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected REWARD_MINT=ACCOUNT[,ACCOUNT...], got {}", value))?;
+    let parse = |address: &str| Pubkey::from_str(address.trim()).map_err(|_| format!("invalid address {}", address));
+    let accounts = accounts
+        .split(',')
+        .filter(|address| !address.trim().is_empty())
+        .map(parse)
+        .collect::<Result<_, _>>()?;
+    Ok((parse(mint)?, accounts))
+}
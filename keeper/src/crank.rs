@@ -0,0 +1,389 @@
+// Crank tasks run by the keeper on every poll
+
+use crate::{config::Config, error::KeeperError, rpc};
+use borsh::BorshDeserialize;
+use log::{debug, info};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    account::{from_account, Account},
+    clock::Clock,
+    instruction::AccountMeta,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    sysvar,
+};
+use soldrip::{find_distribution_round_address, DistributionRound, HolderRecord, RewardStream, SolDripState};
+use spl_token::state::Account as TokenAccount;
+
+// Shared handles passed to every crank
+pub struct CrankContext<'a> {
+    pub client: &'a RpcClient,
+    pub config: &'a Config,
+    pub payer: &'a Keypair,
+}
+
+// A unit of keeper work. Each crank decides on its own whether it is due.
+pub trait Crank {
+    fn name(&self) -> &'static str;
+
+    fn run(&self, ctx: &CrankContext) -> Result<(), KeeperError>;
+}
+
+// Cranks DistributeDividends whenever the program's distribution rules
+// (threshold, minimum interval, force-after interval) allow a new round.
+// A round left open by an earlier pass is resumed with the holders it has not paid.
+pub struct DistributionCrank;
+
+impl Crank for DistributionCrank {
+    fn name(&self) -> &'static str {
+        "distribution"
+    }
+
+    fn run(&self, ctx: &CrankContext) -> Result<(), KeeperError> {
+        let state = fetch_state(ctx)?;
+
+        let round_id = match fetch_round(ctx)? {
+            Some(round) if round.is_active => {
+                info!("Resuming distribution round {}", round.round_id);
+                return self.distribute(ctx, &state, round.round_id, true);
+            }
+            Some(round) => round.round_id + 1,
+            None => 1,
+        };
+
+        let clock = fetch_clock(ctx)?;
+        let pool_balance = state.pool_balance;
+
+        if let Err(reason) = state
+            .config
+            .distribution_due(pool_balance, state.last_distribution_timestamp, clock.unix_timestamp)
+        {
+            debug!("Distribution not due ({} lamports in pool): {}", pool_balance, reason);
+            return Ok(());
+        }
+
+        if state.slippage_protection_blocks(clock.unix_timestamp) {
+            info!("Slippage protection active, skipping distribution");
+            return Ok(());
+        }
+
+        info!("Starting distribution of {} lamports", pool_balance);
+        self.distribute(ctx, &state, round_id, false)
+    }
+}
+
+impl DistributionCrank {
+    // Pay every holder that round `round_id` has not paid yet, then close the
+    // round. `resuming` is set when the round is already open on chain.
+    fn distribute(
+        &self,
+        ctx: &CrankContext,
+        state: &SolDripState,
+        round_id: u64,
+        resuming: bool,
+    ) -> Result<(), KeeperError> {
+        let config = ctx.config;
+        let holders = resolve_recipients(ctx, fetch_holder_records(ctx)?, round_id)?;
+
+        // The first DistributeDividends opens a new round; without one there is nothing to close
+        if holders.is_empty() && !resuming {
+            info!("No holder records to pay for mint {}, not starting a round", state.mint);
+            return Ok(());
+        }
+        if holders.is_empty() {
+            info!("No token holders left to pay for mint {}", state.mint);
+        }
+
+        for (index, batch) in holders.chunks(config.batch_size.max(1)).enumerate() {
+            let instruction = soldrip::distribute_dividends(
+                &config.program_id,
+                &ctx.payer.pubkey(),
+                &state.dividend_pool,
+                &config.state,
+                batch,
+            )
+            .map_err(|_| KeeperError::InvalidAccountData("DistributeDividends instruction".to_string()))?;
+
+            let label = format!("distribute batch {}", index);
+            match rpc::submit(ctx.client, config, ctx.payer, &[instruction], &label)? {
+                Some(signature) => info!("{} confirmed: {}", label, signature),
+                None => info!("{} simulated ({} holders)", label, batch.len()),
+            }
+        }
+
+        // In dry-run mode no round was opened on chain, so there is nothing to close
+        if config.dry_run {
+            return Ok(());
+        }
+
+        let instruction = soldrip::complete_distribution_round(&config.program_id, &ctx.payer.pubkey(), &config.state)
+            .map_err(|_| KeeperError::InvalidAccountData("CompleteDistributionRound instruction".to_string()))?;
+        if let Some(signature) = rpc::submit(ctx.client, config, ctx.payer, &[instruction], "complete round")? {
+            info!("Distribution round completed: {}", signature);
+        }
+
+        Ok(())
+    }
+}
+
+// Books lamports sent straight to the dividend pool, outside DepositDividends,
+// so they are distributed in the next round
+pub struct SyncPoolCrank;
+
+impl Crank for SyncPoolCrank {
+    fn name(&self) -> &'static str {
+        "sync-pool"
+    }
+
+    fn run(&self, ctx: &CrankContext) -> Result<(), KeeperError> {
+        let config = ctx.config;
+        let state = fetch_state(ctx)?;
+
+        let pool_lamports = rpc::with_retry(config, "get_balance", || {
+            Ok(ctx.client.get_balance(&state.dividend_pool)?)
+        })?;
+        let rent_reserve = rpc::with_retry(config, "get_minimum_balance_for_rent_exemption", || {
+            Ok(ctx.client.get_minimum_balance_for_rent_exemption(0)?)
+        })?;
+
+        let booked = rent_reserve + state.booked_pool_lamports();
+        if pool_lamports <= booked {
+            return Ok(());
+        }
+
+        info!("Booking {} lamports donated to the dividend pool", pool_lamports - booked);
+        let instruction = soldrip::sync_pool(&config.program_id, &config.state)
+            .map_err(|_| KeeperError::InvalidAccountData("SyncPool instruction".to_string()))?;
+        if let Some(signature) = rpc::submit(ctx.client, config, ctx.payer, &[instruction], "sync pool")? {
+            info!("Dividend pool synced: {}", signature);
+        }
+
+        Ok(())
+    }
+}
+
+// Swaps the DRIP tax collected by each reward stream into its reward token
+// with ConvertStreamTax. The minimum output is the simulated output less
+// `convert_slippage_bps`, so a sandwich can take no more than that. Only the
+// authority or the configured stream converter may convert.
+pub struct StreamConversionCrank;
+
+impl Crank for StreamConversionCrank {
+    fn name(&self) -> &'static str {
+        "stream-conversion"
+    }
+
+    fn run(&self, ctx: &CrankContext) -> Result<(), KeeperError> {
+        let config = ctx.config;
+        let state = fetch_state(ctx)?;
+        if state.reward_stream_count == 0 || state.config.swap_program == Pubkey::default() {
+            return Ok(());
+        }
+        if !state.can_convert_stream_tax(&ctx.payer.pubkey()) {
+            debug!("{} may not convert reward stream tax", ctx.payer.pubkey());
+            return Ok(());
+        }
+
+        for (address, stream) in fetch_reward_streams(ctx)? {
+            let tax_collected = fetch_token_amount(ctx, &stream.tax_account)?;
+            if tax_collected == 0 {
+                continue;
+            }
+
+            let swap_accounts: Vec<AccountMeta> = config
+                .swap_accounts_for(&stream.reward_mint)
+                .iter()
+                .map(|account| AccountMeta::new(*account, false))
+                .collect();
+            let instruction = |minimum_amount_out| {
+                soldrip::convert_stream_tax(
+                    &config.program_id,
+                    &ctx.payer.pubkey(),
+                    &config.state,
+                    &stream.reward_mint,
+                    &state.config.swap_program,
+                    &swap_accounts,
+                    minimum_amount_out,
+                )
+                .map_err(|_| KeeperError::InvalidAccountData("ConvertStreamTax instruction".to_string()))
+            };
+
+            // Quote the swap with the smallest minimum the program accepts
+            let label = format!("convert stream {}", address);
+            let expected = rpc::simulate_token_gain(ctx.client, config, ctx.payer, &[instruction(1)?], &stream.vault, &label)?;
+            let minimum_amount_out = minimum_amount_out(expected, config.convert_slippage_bps);
+            if minimum_amount_out == 0 {
+                debug!("{}: {} DRIP would swap to nothing, skipping", label, tax_collected);
+                continue;
+            }
+
+            info!("Converting {} DRIP for stream {}, at least {} reward tokens", tax_collected, address, minimum_amount_out);
+            match rpc::submit(ctx.client, config, ctx.payer, &[instruction(minimum_amount_out)?], &label)? {
+                Some(signature) => info!("{} confirmed: {}", label, signature),
+                None => info!("{} simulated", label),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// The simulated output less the tolerated slippage
+fn minimum_amount_out(expected: u64, slippage_bps: u16) -> u64 {
+    (expected as u128 * (10_000 - slippage_bps.min(10_000)) as u128 / 10_000) as u64
+}
+
+// Liquidity is not cranked: the program has no instruction that adds the LP
+// share of the tax to a DEX pool. It accumulates in the LP pool account for
+// the authority to deploy.
+
+fn fetch_account(ctx: &CrankContext, address: &Pubkey) -> Result<Account, KeeperError> {
+    rpc::with_retry(ctx.config, "get_account", || {
+        ctx.client
+            .get_account_with_commitment(address, ctx.client.commitment())?
+            .value
+            .ok_or_else(|| KeeperError::AccountNotFound(address.to_string()))
+    })
+}
+
+pub fn fetch_state(ctx: &CrankContext) -> Result<SolDripState, KeeperError> {
+    let account = fetch_account(ctx, &ctx.config.state)?;
+    SolDripState::try_from_slice(&account.data)
+        .map_err(|_| KeeperError::InvalidAccountData(ctx.config.state.to_string()))
+}
+
+// The round account is created by the first distribution, so it may not exist yet
+fn fetch_round(ctx: &CrankContext) -> Result<Option<DistributionRound>, KeeperError> {
+    let (address, _) = find_distribution_round_address(&ctx.config.program_id, &ctx.config.state);
+    let account = rpc::with_retry(ctx.config, "get_account", || {
+        Ok(ctx.client.get_account_with_commitment(&address, ctx.client.commitment())?.value)
+    })?;
+
+    account
+        .map(|account| {
+            DistributionRound::try_from_slice(&account.data)
+                .map_err(|_| KeeperError::InvalidAccountData(address.to_string()))
+        })
+        .transpose()
+}
+
+// Every reward stream of the state account
+fn fetch_reward_streams(ctx: &CrankContext) -> Result<Vec<(Pubkey, RewardStream)>, KeeperError> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(RewardStream::LEN as u64),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, ctx.config.state.as_ref())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+
+    let accounts = rpc::with_retry(ctx.config, "get_program_accounts", || {
+        Ok(ctx.client.get_program_accounts_with_config(&ctx.config.program_id, config.clone())?)
+    })?;
+
+    accounts
+        .into_iter()
+        .map(|(address, account)| {
+            RewardStream::try_from_slice(&account.data)
+                .map(|stream| (address, stream))
+                .map_err(|_| KeeperError::InvalidAccountData(address.to_string()))
+        })
+        .collect()
+}
+
+fn fetch_token_amount(ctx: &CrankContext, address: &Pubkey) -> Result<u64, KeeperError> {
+    let account = fetch_account(ctx, address)?;
+    TokenAccount::unpack(&account.data)
+        .map(|token| token.amount)
+        .map_err(|_| KeeperError::InvalidAccountData(address.to_string()))
+}
+
+fn fetch_clock(ctx: &CrankContext) -> Result<Clock, KeeperError> {
+    let account = fetch_account(ctx, &sysvar::clock::id())?;
+    from_account::<Clock, _>(&account)
+        .ok_or_else(|| KeeperError::InvalidAccountData(sysvar::clock::id().to_string()))
+}
+
+// Every holder record of the state account, sorted by token account
+pub fn fetch_holder_records(ctx: &CrankContext) -> Result<Vec<HolderRecord>, KeeperError> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(HolderRecord::LEN as u64),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, ctx.config.state.as_ref())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+
+    let accounts = rpc::with_retry(ctx.config, "get_program_accounts", || {
+        Ok(ctx.client.get_program_accounts_with_config(&ctx.config.program_id, config.clone())?)
+    })?;
+
+    let mut records: Vec<HolderRecord> = accounts
+        .into_iter()
+        .filter_map(|(_, account)| HolderRecord::try_from_slice(&account.data).ok())
+        .collect();
+    records.sort_by_key(|record| record.token_account);
+
+    Ok(records)
+}
+
+// Pair each holder record that round `round_id` still owes with its dividend
+// recipient: the payout address from the record if one is set, otherwise the
+// token account owner. Records of closed token accounts are passed too, so
+// the program stops counting their weight, as long as they still count any.
+fn resolve_recipients(
+    ctx: &CrankContext,
+    records: Vec<HolderRecord>,
+    round_id: u64,
+) -> Result<Vec<(Pubkey, Pubkey)>, KeeperError> {
+    let records: Vec<HolderRecord> = records.into_iter().filter(|record| record.paid_round != round_id).collect();
+    let mut resolved = Vec::with_capacity(records.len());
+
+    // getMultipleAccounts accepts at most 100 addresses per call
+    for chunk in records.chunks(100) {
+        let token_accounts: Vec<Pubkey> = chunk.iter().map(|record| record.token_account).collect();
+        let accounts = rpc::with_retry(ctx.config, "get_multiple_accounts", || {
+            Ok(ctx.client.get_multiple_accounts(&token_accounts)?)
+        })?;
+
+        for (record, account) in chunk.iter().zip(accounts) {
+            let token = account
+                .and_then(|account| TokenAccount::unpack(&account.data).ok())
+                .map(|token| (token.owner, token.amount));
+            if let Some(recipient) = record.distribution_recipient(token) {
+                resolved.push((record.token_account, recipient));
+            }
+        }
+    }
+
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimum_amount_out_allows_the_configured_slippage() {
+        assert_eq!(minimum_amount_out(10_000, 100), 9_900);
+        assert_eq!(minimum_amount_out(10_000, 0), 10_000);
+        assert_eq!(minimum_amount_out(10_000, 10_000), 0);
+        assert_eq!(minimum_amount_out(u64::MAX, 1), (u64::MAX as u128 * 9_999 / 10_000) as u64);
+        assert_eq!(minimum_amount_out(99, 100), 98);
+    }
+}
@@ -0,0 +1,35 @@
+// Keeper error type
+
+use solana_client::client_error::ClientError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeeperError {
+    #[error("RPC error: {0}")]
+    Rpc(Box<ClientError>),
+
+    #[error("Failed to read keypair {0}")]
+    Keypair(String),
+
+    #[error("Account {0} not found")]
+    AccountNotFound(String),
+
+    #[error("Failed to decode account {0}")]
+    InvalidAccountData(String),
+
+    #[error("Simulation failed: {0}")]
+    Simulation(String),
+}
+
+impl From<ClientError> for KeeperError {
+    fn from(e: ClientError) -> Self {
+        KeeperError::Rpc(Box::new(e))
+    }
+}
+
+impl KeeperError {
+    // Transport and RPC failures are worth retrying; bad configuration or
+    // a transaction the program rejects in simulation is not
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, KeeperError::Rpc(_))
+    }
+}
@@ -0,0 +1,66 @@
+// SolDrip keeper
+// Watches the dividend pool and cranks distributions and housekeeping transactions
+
+mod config;
+mod crank;
+mod error;
+mod rpc;
+
+use clap::Parser;
+use config::Config;
+use crank::{Crank, CrankContext, DistributionCrank, StreamConversionCrank, SyncPoolCrank};
+use error::KeeperError;
+use log::{error, info};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::read_keypair_file, signer::Signer};
+use std::{process, thread};
+
+// All cranks run on every poll, in order
+fn cranks() -> Vec<Box<dyn Crank>> {
+    vec![Box::new(SyncPoolCrank), Box::new(StreamConversionCrank), Box::new(DistributionCrank)]
+}
+
+fn run(config: Config) -> Result<(), KeeperError> {
+    let keypair_path = config.keypair_path();
+    let payer = read_keypair_file(&keypair_path)
+        .map_err(|_| KeeperError::Keypair(keypair_path.display().to_string()))?;
+    let client = RpcClient::new_with_commitment(config.rpc_url.clone(), CommitmentConfig::confirmed());
+
+    info!(
+        "Keeper {} watching state {} on {}{}",
+        payer.pubkey(),
+        config.state,
+        config.rpc_url,
+        if config.dry_run { " (dry run)" } else { "" },
+    );
+
+    let ctx = CrankContext {
+        client: &client,
+        config: &config,
+        payer: &payer,
+    };
+    let cranks = cranks();
+
+    loop {
+        for crank in &cranks {
+            // A failing crank is logged and retried on the next poll
+            if let Err(err) = crank.run(&ctx) {
+                error!("{} crank failed: {}", crank.name(), err);
+            }
+        }
+
+        if config.once {
+            return Ok(());
+        }
+        thread::sleep(config.poll_interval());
+    }
+}
+
+fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    if let Err(err) = run(Config::parse()) {
+        error!("{}", err);
+        process::exit(1);
+    }
+}
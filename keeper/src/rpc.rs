@@ -0,0 +1,205 @@
+// RPC helpers: retry with exponential backoff and transaction submission
+
+use crate::{config::Config, error::KeeperError};
+use log::{info, warn};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig},
+};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::Transaction,
+};
+use spl_token::state::Account as TokenAccount;
+use std::{thread, time::Duration};
+
+// Run `op` until it succeeds or `max_retries` attempts have failed
+pub fn with_retry<T, F>(config: &Config, what: &str, mut op: F) -> Result<T, KeeperError>
+where
+    F: FnMut() -> Result<T, KeeperError>,
+{
+    let mut backoff = Duration::from_millis(config.backoff_ms);
+    let max_backoff = Duration::from_millis(config.max_backoff_ms);
+    let mut attempt = 1;
+
+    loop {
+        match op() {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt < config.max_retries && err.is_retryable() => {
+                warn!("{} failed (attempt {}/{}): {}; retrying in {:?}", what, attempt, config.max_retries, err, backoff);
+                thread::sleep(backoff);
+                backoff = next_backoff(backoff, max_backoff);
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+// Double the backoff after a failed attempt, up to `max_backoff`
+fn next_backoff(backoff: Duration, max_backoff: Duration) -> Duration {
+    (backoff * 2).min(max_backoff)
+}
+
+// Sign and send a transaction, or only simulate it in dry-run mode.
+// Returns `None` when the transaction was not submitted.
+pub fn submit(
+    client: &RpcClient,
+    config: &Config,
+    payer: &Keypair,
+    instructions: &[Instruction],
+    label: &str,
+) -> Result<Option<Signature>, KeeperError> {
+    with_retry(config, label, || {
+        // Fetch a fresh blockhash on every attempt so retries are not rejected as expired
+        let blockhash = client.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            blockhash,
+        );
+
+        if config.dry_run {
+            let simulation = client.simulate_transaction(&transaction)?.value;
+            for line in simulation.logs.unwrap_or_default() {
+                info!("[dry-run] {}: {}", label, line);
+            }
+            return match simulation.err {
+                Some(err) => Err(KeeperError::Simulation(err.to_string())),
+                None => Ok(None),
+            };
+        }
+
+        let signature = client.send_and_confirm_transaction(&transaction)?;
+        Ok(Some(signature))
+    })
+}
+
+// Simulate a transaction and return how many tokens the token account
+// `watched` would gain from it
+pub fn simulate_token_gain(
+    client: &RpcClient,
+    config: &Config,
+    payer: &Keypair,
+    instructions: &[Instruction],
+    watched: &Pubkey,
+    label: &str,
+) -> Result<u64, KeeperError> {
+    let token_amount = |account: Option<Account>| {
+        account
+            .and_then(|account| TokenAccount::unpack(&account.data).ok())
+            .map(|token| token.amount)
+            .ok_or_else(|| KeeperError::InvalidAccountData(watched.to_string()))
+    };
+
+    with_retry(config, label, || {
+        let before = token_amount(client.get_account_with_commitment(watched, client.commitment())?.value)?;
+
+        let blockhash = client.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &[payer], blockhash);
+        let simulation = client
+            .simulate_transaction_with_config(
+                &transaction,
+                RpcSimulateTransactionConfig {
+                    accounts: Some(RpcSimulateTransactionAccountsConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        addresses: vec![watched.to_string()],
+                    }),
+                    ..RpcSimulateTransactionConfig::default()
+                },
+            )?
+            .value;
+        if let Some(err) = simulation.err {
+            return Err(KeeperError::Simulation(err.to_string()));
+        }
+
+        let after = simulation
+            .accounts
+            .and_then(|accounts| accounts.into_iter().next().flatten())
+            .and_then(|account| account.decode::<Account>());
+        Ok(token_amount(after)?.saturating_sub(before))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use solana_client::client_error::{ClientError, ClientErrorKind};
+    use std::cell::Cell;
+
+    fn config(max_retries: u32) -> Config {
+        let program_id = Pubkey::new_unique().to_string();
+        let state = Pubkey::new_unique().to_string();
+        let max_retries = max_retries.to_string();
+        Config::parse_from([
+            "soldrip-keeper",
+            "--program-id",
+            &program_id,
+            "--state",
+            &state,
+            "--max-retries",
+            &max_retries,
+            "--backoff-ms",
+            "0",
+        ])
+    }
+
+    fn rpc_error() -> KeeperError {
+        ClientError::from(ClientErrorKind::Custom("connection reset".to_string())).into()
+    }
+
+    #[test]
+    fn retries_rpc_errors_until_success() {
+        let attempts = Cell::new(0);
+        let result = with_retry(&config(5), "op", || {
+            attempts.set(attempts.get() + 1);
+            match attempts.get() {
+                1..=3 => Err(rpc_error()),
+                _ => Ok(attempts.get()),
+            }
+        });
+        assert_eq!(result.unwrap(), 4);
+    }
+
+    #[test]
+    fn gives_up_after_max_retries() {
+        let attempts = Cell::new(0);
+        let result: Result<(), _> = with_retry(&config(3), "op", || {
+            attempts.set(attempts.get() + 1);
+            Err(rpc_error())
+        });
+        assert!(matches!(result, Err(KeeperError::Rpc(_))));
+        assert_eq!(attempts.get(), 3);
+    }
+
+    #[test]
+    fn does_not_retry_rejected_transactions() {
+        let attempts = Cell::new(0);
+        let result: Result<(), _> = with_retry(&config(5), "op", || {
+            attempts.set(attempts.get() + 1);
+            Err(KeeperError::Simulation("custom program error: 0x1".to_string()))
+        });
+        assert!(matches!(result, Err(KeeperError::Simulation(_))));
+        assert_eq!(attempts.get(), 1);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let max = Duration::from_millis(3_000);
+        let mut backoff = Duration::from_millis(500);
+        let mut delays = Vec::new();
+        for _ in 0..5 {
+            delays.push(backoff.as_millis());
+            backoff = next_backoff(backoff, max);
+        }
+        assert_eq!(delays, [500, 1_000, 2_000, 3_000, 3_000]);
+    }
+}
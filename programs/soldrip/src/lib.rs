@@ -2,18 +2,23 @@
 // This program implements a token with transaction tax and dividend distribution
 
 use borsh::{BorshDeserialize, BorshSerialize};
+use shank::{ShankAccount, ShankInstruction};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    entrypoint,
     entrypoint::ProgramResult,
     msg,
+    hash::hashv,
     program_error::ProgramError,
     pubkey::Pubkey,
-    program::invoke,
+    log::sol_log_data,
+    program::{invoke, invoke_signed, set_return_data},
     program_pack::Pack,
     system_instruction,
-    sysvar::{rent::Rent, Sysvar, clock::Clock},
+    system_program,
+    sysvar::{self, rent::Rent, Sysvar, clock::Clock},
+    instruction::{AccountMeta, Instruction},
 };
+use spl_associated_token_account::get_associated_token_address;
 use spl_token::{
     instruction as token_instruction,
     state::{Account as TokenAccount, Mint},
@@ -21,29 +26,64 @@ use spl_token::{
 use std::convert::TryInto;
 
 // Program entrypoint
-entrypoint!(process_instruction);
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
 
 // Program ID
 solana_program::declare_id!("SoLDripTokenProgramID111111111111111111111111");
 
 // Constants
-const TAX_PERCENTAGE: u8 = 5; // 5% standard tax
-const HIGH_TAX_PERCENTAGE: u8 = 8; // 8% tax for large sales
-const LP_TAX_PERCENTAGE: u8 = 1; // 1% goes to LP
-const DIVIDEND_TAX_PERCENTAGE: u8 = 4; // 4% goes to dividend pool
-const HIGH_DIVIDEND_TAX_PERCENTAGE: u8 = 7; // 7% goes to dividend pool for large sales
-const DISTRIBUTION_THRESHOLD: u64 = 100_000_000; // 0.1 SOL in lamports
-const MAX_HOLDING_PERCENTAGE: u8 = 3; // 3% maximum holding
-const LARGE_SALE_THRESHOLD_PERCENTAGE: u8 = 2; // 0.2% of total supply is considered large sale
+pub const BPS_DENOMINATOR: u64 = 10_000; // Basis points per 100%
+const TAX_BPS: u16 = 500; // 5% standard tax
+const LP_TAX_BPS: u16 = 100; // 1% goes to LP
+const DIVIDEND_TAX_BPS: u16 = 400; // 4% goes to dividend pool
+const HIGH_DIVIDEND_TAX_BPS: u16 = 700; // 7% goes to dividend pool for large sales
+pub const DISTRIBUTION_THRESHOLD: u64 = 100_000_000; // 0.1 SOL in lamports
+const DEFAULT_MIN_DISTRIBUTION_INTERVAL: i64 = 300; // 5 minutes between distribution rounds
+const DEFAULT_FORCE_DISTRIBUTION_AFTER: i64 = 3600; // Distribute below the threshold after 1 hour
+const DEFAULT_LAUNCH_TAX_BPS: u16 = 2500; // 25% tax right after trading opens
+const DEFAULT_LAUNCH_TAX_SLOTS: u64 = 150; // Launch tax decays to the standard tax over ~1 minute
+const MAX_HOLDING_BPS: u16 = 300; // 3% maximum holding
+const DEFAULT_SELL_COOLDOWN: i64 = 60; // 1 minute between sells from the same wallet
+const LARGE_SALE_THRESHOLD_BPS: u16 = 20; // 0.2% of total supply is considered large sale
+pub const MAX_TAX_TIERS: usize = 5; // Size tiers in the sell tax schedule
+const MAX_TIER_TAX_BPS: u16 = 5000; // 50% upper bound on a single tier's total tax
 const PRICE_FLUCTUATION_THRESHOLD_BPS: u16 = 1500; // 15% price fluctuation threshold
-const SLIPPAGE_PROTECTION_DURATION: i64 = 600; // 10 minutes in seconds
+pub const SLIPPAGE_PROTECTION_DURATION: i64 = 600; // 10 minutes in seconds
+const SLIPPAGE_BUYBACK_BPS: u16 = 8000; // 80% of the tax used for buybacks in slippage protection mode
 const HOLDING_BONUS_DAYS: u64 = 7; // 7 days for bonus
-const HOLDING_BONUS_MULTIPLIER: u64 = 110; // 1.1x multiplier (110%)
-const HOLDING_BONUS_DIVISOR: u64 = 100; // Divisor for percentage calculation
-const GAS_COST_PERCENTAGE: u8 = 2; // 2% for gas costs
+const HOLDING_BONUS_MULTIPLIER_BPS: u64 = 11_000; // 1.1x dividend weight multiplier
+const BASE_MULTIPLIER_BPS: u64 = BPS_DENOMINATOR; // 1x dividend weight multiplier
+const MAX_COIN_AGE_MULTIPLIER_BPS: u64 = 30_000; // 3x upper bound on any coin-age multiplier
+pub const MAX_COIN_AGE_TIERS: usize = 4; // Steps in a tiered coin-age curve
+const SECONDS_PER_DAY: i64 = 86_400;
+const GAS_COST_BPS: u16 = 200; // 2% for gas costs, paid to distribution cranks
+const CRANK_REWARD_PER_HOLDER: u64 = 100_000; // 0.0001 SOL to the crank for each holder it pays
+pub const DISTRIBUTION_ROUND_TIMEOUT: i64 = 3600; // 1 hour before anyone may close a stale round
+pub const DISTRIBUTION_ROUND_SEED: &[u8] = b"distribution_round";
+pub const DISTRIBUTION_HISTORY_SEED: &[u8] = b"distribution_history";
+pub const DISTRIBUTION_HISTORY_CAPACITY: usize = 64; // Completed rounds kept on chain
+pub const DIVIDEND_POOL_SEED: &[u8] = b"dividend_pool";
+pub const HOLDER_RECORD_SEED: &[u8] = b"holder";
+pub const SELLER_RECORD_SEED: &[u8] = b"seller";
+const DEFAULT_MAX_COMPOUND_HOLDING_BPS: u16 = 500; // Compounding may grow a holding up to 5%
+pub const REWARD_STREAM_SEED: &[u8] = b"reward_stream";
+pub const REWARD_VAULT_SEED: &[u8] = b"reward_vault";
+pub const REWARD_TAX_SEED: &[u8] = b"reward_tax";
+pub const REWARD_POSITION_SEED: &[u8] = b"reward_position";
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000; // Fixed-point scale of reward_per_token
+pub const TREASURY_VAULT_SEED: &[u8] = b"treasury_vault";
+const DEFAULT_TREASURY_WITHDRAW_PERIOD: i64 = 86_400; // Treasury withdrawal limit resets daily
+pub const MERKLE_DISTRIBUTION_SEED: &[u8] = b"merkle_distribution";
+pub const MERKLE_CLAIM_BITMAP_SEED: &[u8] = b"merkle_claims";
+pub const MERKLE_CLAIM_BITMAP_BYTES: usize = 8192; // Fits the 10 KiB limit on accounts created through CPI
+pub const MERKLE_CLAIMS_PER_BITMAP: u32 = MERKLE_CLAIM_BITMAP_BYTES as u32 * 8;
+pub const MERKLE_CLAIM_PERIOD: i64 = 30 * SECONDS_PER_DAY; // Unclaimed Merkle dividends return to the pool after 30 days
+const MERKLE_LEAF_PREFIX: u8 = 0; // Domain separation between leaf and node hashes
+const MERKLE_NODE_PREFIX: u8 = 1;
 
 // Error codes
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
 pub enum SolDripError {
     #[error("Invalid instruction")]
     InvalidInstruction,
@@ -65,6 +105,129 @@ pub enum SolDripError {
     
     #[error("Not enough tokens")]
     InsufficientTokenBalance,
+    
+    #[error("Invalid distribution round account")]
+    InvalidDistributionRound,
+    
+    #[error("No distribution round in progress")]
+    NoActiveDistributionRound,
+    
+    #[error("Distribution round still in progress")]
+    DistributionRoundInProgress,
+    
+    #[error("Invalid dividend pool account")]
+    InvalidDividendPool,
+    
+    #[error("Invalid holder record account")]
+    InvalidHolderRecord,
+    
+    #[error("Dividend recipient does not match the holder's payout address")]
+    InvalidPayoutRecipient,
+    
+    #[error("Signer does not own the token account")]
+    NotTokenAccountOwner,
+    
+    #[error("Signer is not the program authority")]
+    Unauthorized,
+    
+    #[error("No swap adapter configured")]
+    SwapAdapterNotConfigured,
+    
+    #[error("Swap returned less than the minimum amount out")]
+    SwapOutputTooLow,
+    
+    #[error("No dividends to claim")]
+    NothingToClaim,
+    
+    #[error("Invalid reward stream account")]
+    InvalidRewardStream,
+    
+    #[error("Reward stream tax shares exceed 100% of the dividend tax")]
+    RewardStreamShareTooHigh,
+    
+    #[error("Every reward stream must be passed once, in ascending order")]
+    MissingRewardStreams,
+    
+    #[error("Minimum distribution interval has not elapsed")]
+    DistributionTooSoon,
+    
+    #[error("Dividend pool lamports do not cover its booked balances")]
+    PoolBalanceMismatch,
+    
+    #[error("Trading is not enabled yet")]
+    TradingNotEnabled,
+    
+    #[error("Trading is already enabled")]
+    TradingAlreadyEnabled,
+    
+    #[error("Transfer exceeds maximum transaction amount")]
+    ExceedsMaxTransactionAmount,
+    
+    #[error("Sell cooldown still active")]
+    SellCooldownActive,
+    
+    #[error("Invalid tax tier schedule")]
+    InvalidTaxTiers,
+    
+    #[error("Treasury vault not initialized")]
+    TreasuryNotInitialized,
+    
+    #[error("Invalid treasury vault")]
+    InvalidTreasuryVault,
+    
+    #[error("Treasury withdrawal limit exceeded for this period")]
+    TreasuryWithdrawalLimitExceeded,
+    
+    #[error("Invalid coin-age curve")]
+    InvalidCoinAgeCurve,
+    
+    #[error("Invalid distribution history account")]
+    InvalidDistributionHistory,
+    
+    #[error("Program is paused")]
+    ProgramPaused,
+    
+    #[error("Invalid Merkle distribution")]
+    InvalidMerkleDistribution,
+    
+    #[error("Invalid Merkle proof")]
+    InvalidMerkleProof,
+    
+    #[error("Invalid Merkle claim bitmap")]
+    InvalidClaimBitmap,
+    
+    #[error("Merkle distribution already claimed")]
+    AlreadyClaimed,
+    
+    #[error("Invalid configuration value")]
+    InvalidConfig,
+    
+    #[error("Token holder already paid in this round")]
+    HolderAlreadyPaid,
+    
+    #[error("Invalid seller record")]
+    InvalidSellerRecord,
+    
+    #[error("Swap needs a nonzero minimum amount out")]
+    MinimumAmountOutRequired,
+    
+    #[error("Merkle distribution claim period has ended")]
+    MerkleDistributionExpired,
+    
+    #[error("Merkle distribution claim period has not ended")]
+    MerkleDistributionNotExpired,
+    
+    #[error("Token account does not earn dividends")]
+    ExcludedFromDividends,
+    
+    #[error("Invalid LP pool account")]
+    InvalidLpPool,
+    
+    #[error("Swap spent more than the amount in")]
+    SwapInputTooHigh,
+    
+    #[error("Swap accounts may not include the dividend pool's token accounts")]
+    InvalidSwapAccount,
 }
 
 impl From<SolDripError> for ProgramError {
@@ -74,34 +237,46 @@ impl From<SolDripError> for ProgramError {
 }
 
 // Instruction types
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, ShankInstruction)]
 pub enum SolDripInstruction {
-    /// Initialize a new SolDrip token
-    /// 
-    /// Accounts expected:
-    /// 0. `[signer]` The account of the person initializing the token
-    /// 1. `[writable]` The token mint account
-    /// 2. `[writable]` The dividend pool account
-    /// 3. `[writable]` The LP pool account
-    /// 4. `[writable]` The state account
-    /// 5. `[]` The rent sysvar
-    /// 6. `[]` The token program
+    /// Initialize a new SolDrip token. The LP pool is created as a token
+    /// account of the mint held by the dividend pool. The dividend pool's
+    /// associated token account, which collects the dividend tax, must be
+    /// created before the first taxed transfer.
+    #[account(0, writable, signer, name="authority", desc="The account of the person initializing the token")]
+    #[account(1, writable, signer, name="mint", desc="The token mint account")]
+    #[account(2, writable, name="dividend_pool", desc="The dividend pool account (PDA)")]
+    #[account(3, writable, signer, name="lp_pool", desc="The LP pool token account")]
+    #[account(4, writable, signer, name="state", desc="The state account")]
+    #[account(5, name="rent", desc="The rent sysvar")]
+    #[account(6, name="token_program", desc="The token program")]
     Initialize {
         /// Total supply of tokens
         total_supply: u64,
     },
     
-    /// Transfer tokens with tax
+    /// Transfer tokens with tax. `TransferWithTax` builds the seller record
+    /// from the sender, so delegated sells must pass the owner's instead.
     /// 
-    /// Accounts expected:
-    /// 0. `[signer]` The account of the person sending tokens
-    /// 1. `[writable]` The source token account
-    /// 2. `[writable]` The destination token account
-    /// 3. `[writable]` The dividend pool account
-    /// 4. `[writable]` The LP pool account
-    /// 5. `[writable]` The state account
-    /// 6. `[]` The token program
-    /// 7. `[]` The clock sysvar
+    /// Then, for every reward stream in ascending address order:
+    /// 0. `[writable]` The reward stream account (PDA)
+    /// 1. `[writable]` The stream's DRIP tax token account (PDA)
+    /// 2. `[writable]` The source's reward position (PDA, may be uninitialized)
+    /// 3. `[writable]` The destination's reward position (PDA, may be uninitialized)
+    #[account(0, writable, signer, name="sender", desc="The account of the person sending tokens; pays for missing holder and seller records")]
+    #[account(1, writable, name="source", desc="The source token account")]
+    #[account(2, writable, name="destination", desc="The destination token account")]
+    #[account(3, writable, name="dividend_tax_account", desc="The dividend pool's associated token account")]
+    #[account(4, writable, name="lp_pool", desc="The LP pool token account")]
+    #[account(5, writable, name="state", desc="The state account")]
+    #[account(6, name="token_program", desc="The token program")]
+    #[account(7, name="clock", desc="The clock sysvar")]
+    #[account(8, writable, name="source_holder_record", desc="The source's holder record (PDA, may be uninitialized)")]
+    #[account(9, writable, name="mint", desc="The token mint, for burning the burn share of the tax")]
+    #[account(10, writable, name="treasury_vault", desc="The treasury vault (PDA)")]
+    #[account(11, writable, name="destination_holder_record", desc="The destination's holder record (PDA, created if uninitialized unless the transfer is a sell)")]
+    #[account(12, name="system_program", desc="The system program")]
+    #[account(13, writable, name="seller_record", desc="The seller record of the source's owner (PDA, created on the first sell)")]
     TransferWithTax {
         /// Amount of tokens to transfer
         amount: u64,
@@ -109,18 +284,734 @@ pub enum SolDripInstruction {
     
     /// Distribute dividends to token holders
     /// 
-    /// Accounts expected:
-    /// 0. `[signer]` The account initiating the distribution
-    /// 1. `[writable]` The dividend pool account
-    /// 2. `[writable]` The state account
-    /// 3. `[]` The clock sysvar
-    /// 4. `[]` The token program
-    /// 5+ `[writable]` Token holder accounts
+    /// Starts a new distribution round if none is in progress, otherwise
+    /// continues the current one. A round starts once the configured
+    /// minimum interval has passed and the pool holds at least 0.1 SOL,
+    /// or the force-after interval has passed. Token holders may be passed
+    /// in any order; each holder record is flagged when paid, and a holder
+    /// already paid in the round is rejected. Dividends are paid to the
+    /// holder's payout address if one is set, otherwise to the token
+    /// account owner.
+    /// 
+    /// The distributor is paid from the round's gas reserve in proportion
+    /// to the share of the round it distributed, capped per call.
+    /// 
+    /// Then, for each token holder:
+    /// 0. `[]` The token holder account
+    /// 1. `[writable]` The holder record (PDA, may be uninitialized)
+    /// 2. `[writable]` The dividend recipient wallet
+    /// 
+    /// Shares are taken of the total dividend weight counted by holder
+    /// records, so token accounts without a holder record earn nothing.
+    /// Every holder is recounted at its live balance once paid, and a
+    /// holder whose token account was closed stops counting; its recipient
+    /// is not used. Balance changes outside `TransferWithTax`, such as DEX
+    /// swaps, are only seen this way, so distributors should pass every
+    /// holder record each round. Each holder is paid on at most the weight
+    /// it counted when the round started; balance changes during the round
+    /// count from the next round on.
+    /// Holders with compounding enabled are credited in their holder
+    /// record instead of being paid, and compound through `ClaimDividends`.
+    /// With snapshot eligibility enabled (the default), holders earn on the
+    /// lowest balance their holder record saw during the accrual period
+    /// being paid, including the balance observed when the previous round
+    /// paid them.
+    #[account(0, writable, signer, name="distributor", desc="The account initiating the distribution")]
+    #[account(1, writable, name="dividend_pool", desc="The dividend pool account (PDA)")]
+    #[account(2, writable, name="state", desc="The state account")]
+    #[account(3, writable, name="distribution_round", desc="The distribution round account (PDA)")]
+    #[account(4, name="clock", desc="The clock sysvar")]
+    #[account(5, name="token_program", desc="The token program")]
+    #[account(6, name="system_program", desc="The system program")]
     DistributeDividends,
+    
+    /// Close the current distribution round. Anything left undistributed
+    /// is returned to the pool balance for the next round. The round's
+    /// summary is appended to the distribution history.
+    #[account(0, writable, signer, name="caller", desc="The round starter, or anyone once the round has timed out; pays for the history account on first use")]
+    #[account(1, writable, name="distribution_round", desc="The distribution round account (PDA)")]
+    #[account(2, writable, name="state", desc="The state account")]
+    #[account(3, name="clock", desc="The clock sysvar")]
+    #[account(4, writable, name="distribution_history", desc="The distribution history account (PDA)")]
+    #[account(5, name="system_program", desc="The system program")]
+    CompleteDistributionRound,
+    
+    /// Create the holder record for a token account. The DEX pool, the LP
+    /// pool, the treasury vault and the reward streams' token accounts earn
+    /// no dividends and get no record.
+    /// 
+    /// Then, every reward stream in ascending address order:
+    /// 0. `[]` The reward stream account (PDA)
+    #[account(0, writable, signer, name="payer", desc="The account paying for the holder record")]
+    #[account(1, writable, name="state", desc="The state account")]
+    #[account(2, name="token_holder", desc="The token holder account")]
+    #[account(3, writable, name="holder_record", desc="The holder record (PDA)")]
+    #[account(4, name="system_program", desc="The system program")]
+    InitializeHolder,
+    
+    /// Set the wallet that receives a token account's dividends.
+    /// `Pubkey::default()` reverts to paying the token account owner.
+    #[account(0, signer, name="owner", desc="The token account owner")]
+    #[account(1, name="token_holder", desc="The token holder account")]
+    #[account(2, writable, name="holder_record", desc="The holder record (PDA)")]
+    SetPayoutAddress {
+        /// Wallet that receives dividends
+        payout_address: Pubkey,
+    },
+    
+    /// Opt a token account in or out of auto-compounding
+    #[account(0, signer, name="owner", desc="The token account owner")]
+    #[account(1, name="token_holder", desc="The token holder account")]
+    #[account(2, writable, name="holder_record", desc="The holder record (PDA)")]
+    SetCompounding {
+        /// Reinvest dividends into DRIP instead of paying SOL
+        enabled: bool,
+    },
+    
+    /// Claim dividends accrued in a holder record. With compounding enabled
+    /// the SOL is swapped into DRIP through the swap adapter and deposited
+    /// into the token account, without transfer tax; otherwise it is paid
+    /// to the holder's payout recipient.
+    /// 
+    /// Followed by the accounts forwarded to the swap adapter (compounding
+    /// only), which may not include the LP pool or the dividend pool's token
+    /// account. The swap may spend no more than the claimed SOL.
+    #[account(0, signer, name="owner", desc="The token account owner")]
+    #[account(1, writable, name="state", desc="The state account")]
+    #[account(2, writable, name="dividend_pool", desc="The dividend pool account (PDA)")]
+    #[account(3, writable, name="token_holder", desc="The token holder account")]
+    #[account(4, writable, name="holder_record", desc="The holder record (PDA)")]
+    #[account(5, writable, name="recipient", desc="The dividend recipient wallet")]
+    #[account(6, name="system_program", desc="The system program")]
+    #[account(7, optional, name="swap_program", desc="The swap adapter program (compounding only)")]
+    ClaimDividends {
+        /// Minimum DRIP received when compounding
+        minimum_amount_out: u64,
+    },
+    
+    /// Update the program configuration
+    #[account(0, signer, name="authority", desc="The program authority")]
+    #[account(1, writable, name="state", desc="The state account")]
+    UpdateConfig {
+        /// New configuration
+        config: Box<SolDripConfig>,
+    },
+    
+    /// Open trading, recording the launch slot that the launch tax decays from
+    #[account(0, signer, name="authority", desc="The program authority")]
+    #[account(1, writable, name="state", desc="The state account")]
+    #[account(2, name="clock", desc="The clock sysvar")]
+    EnableTrading,
+    
+    /// Exempt a token account from the maximum transaction amount and
+    /// sell cooldown, or revoke its exemption
+    #[account(0, signer, name="authority", desc="The program authority")]
+    #[account(1, name="state", desc="The state account")]
+    #[account(2, name="token_holder", desc="The token holder account")]
+    #[account(3, writable, name="holder_record", desc="The holder record (PDA)")]
+    SetLimitExemption {
+        /// Exempt the token account from transaction limits
+        exempt: bool,
+    },
+    
+    /// Deposit SOL into the dividend pool, crediting the distributable balance
+    #[account(0, writable, signer, name="depositor", desc="The depositor")]
+    #[account(1, writable, name="state", desc="The state account")]
+    #[account(2, writable, name="dividend_pool", desc="The dividend pool account (PDA)")]
+    #[account(3, name="system_program", desc="The system program")]
+    DepositDividends {
+        /// Lamports to deposit
+        amount: u64,
+    },
+    
+    /// Book lamports sent to the dividend pool outside `DepositDividends`
+    /// as a donation to the distributable balance
+    #[account(0, writable, name="state", desc="The state account")]
+    #[account(1, name="dividend_pool", desc="The dividend pool account (PDA)")]
+    SyncPool,
+    
+    /// Create a reward stream paying holders in an SPL token alongside SOL
+    #[account(0, writable, signer, name="authority", desc="The program authority")]
+    #[account(1, writable, name="state", desc="The state account")]
+    #[account(2, writable, name="reward_stream", desc="The reward stream account (PDA)")]
+    #[account(3, name="reward_mint", desc="The reward mint")]
+    #[account(4, writable, name="reward_vault", desc="The reward vault token account (PDA)")]
+    #[account(5, writable, name="reward_tax_account", desc="The stream's DRIP tax token account (PDA)")]
+    #[account(6, name="mint", desc="The SolDrip token mint")]
+    #[account(7, name="token_program", desc="The token program")]
+    #[account(8, name="system_program", desc="The system program")]
+    CreateRewardStream {
+        /// Share of the dividend tax routed to this stream (basis points)
+        tax_share_bps: u16,
+    },
+    
+    /// Deposit reward tokens into a stream and credit them to the open
+    /// reward positions
+    #[account(0, signer, name="funder", desc="The funder")]
+    #[account(1, name="state", desc="The state account")]
+    #[account(2, writable, name="reward_stream", desc="The reward stream account (PDA)")]
+    #[account(3, writable, name="funder_token_account", desc="The funder's reward token account")]
+    #[account(4, writable, name="reward_vault", desc="The reward vault token account (PDA)")]
+    #[account(5, name="token_program", desc="The token program")]
+    FundRewardStream {
+        /// Amount of reward tokens to deposit
+        amount: u64,
+    },
+    
+    /// Swap the DRIP collected in a stream's tax account into the reward
+    /// token through the swap adapter and credit it to holders. Only the
+    /// authority or the configured `stream_converter` may convert.
+    /// 
+    /// Followed by the accounts forwarded to the swap adapter.
+    #[account(0, signer, name="cranker", desc="The authority or stream converter")]
+    #[account(1, name="state", desc="The state account")]
+    #[account(2, writable, name="reward_stream", desc="The reward stream account (PDA)")]
+    #[account(3, writable, name="reward_tax_account", desc="The stream's DRIP tax token account (PDA)")]
+    #[account(4, writable, name="reward_vault", desc="The reward vault token account (PDA)")]
+    #[account(5, name="swap_program", desc="The swap adapter program")]
+    ConvertStreamTax {
+        /// Minimum reward tokens received, never 0
+        minimum_amount_out: u64,
+    },
+    
+    /// Claim everything a token account is owed across reward streams.
+    /// A stream's position is opened on the first claim, and accrues from then
+    /// on, on the balance `TransferWithTax` settles it at. Rewards are shared
+    /// among open positions only.
+    /// 
+    /// Then, for each reward stream:
+    /// 0. `[writable]` The reward stream account (PDA)
+    /// 1. `[writable]` The reward vault token account (PDA)
+    /// 2. `[writable]` The reward position (PDA)
+    /// 3. `[writable]` The owner's reward token account
+    #[account(0, writable, signer, name="owner", desc="The token account owner")]
+    #[account(1, name="state", desc="The state account")]
+    #[account(2, name="token_holder", desc="The token holder account")]
+    #[account(3, name="token_program", desc="The token program")]
+    #[account(4, name="system_program", desc="The system program")]
+    ClaimRewards,
+    
+    /// Create the treasury vault that collects the treasury share of the tax
+    #[account(0, writable, signer, name="authority", desc="The program authority")]
+    #[account(1, writable, name="state", desc="The state account")]
+    #[account(2, writable, name="treasury_vault", desc="The treasury vault token account (PDA)")]
+    #[account(3, name="mint", desc="The SolDrip token mint")]
+    #[account(4, name="token_program", desc="The token program")]
+    #[account(5, name="system_program", desc="The system program")]
+    InitializeTreasury,
+    
+    /// Withdraw tokens from the treasury vault, limited per period
+    #[account(0, signer, name="treasury_authority", desc="The treasury authority")]
+    #[account(1, writable, name="state", desc="The state account")]
+    #[account(2, writable, name="treasury_vault", desc="The treasury vault (PDA)")]
+    #[account(3, writable, name="destination", desc="The destination token account")]
+    #[account(4, name="token_program", desc="The token program")]
+    #[account(5, name="clock", desc="The clock sysvar")]
+    WithdrawTreasury {
+        /// Amount of tokens to withdraw
+        amount: u64,
+    },
+    
+    /// Quote the tax `TransferWithTax` would charge, without moving any
+    /// tokens. Returns a borsh-serialized `TransferQuote` via return data,
+    /// so clients can call it through `simulateTransaction`.
+    #[account(0, name="state", desc="The state account")]
+    #[account(1, name="clock", desc="The clock sysvar")]
+    #[account(2, name="source", desc="The source token account")]
+    #[account(3, name="destination", desc="The destination token account")]
+    #[account(4, name="source_holder_record", desc="The source's holder record (PDA, may be uninitialized)")]
+    #[account(5, name="seller_record", desc="The seller record of the source's owner (PDA, may be uninitialized)")]
+    QuoteTransfer {
+        /// Amount of tokens to transfer
+        amount: u64,
+        /// Source token account
+        sender: Pubkey,
+        /// Destination token account
+        destination: Pubkey,
+    },
+    
+    /// Report what a token account can claim right now, without claiming.
+    /// Returns a borsh-serialized `ClaimableDividends` via return data.
+    /// 
+    /// Then, for each reward stream:
+    /// 0. `[]` The reward stream account (PDA)
+    /// 1. `[]` The reward vault token account (PDA)
+    /// 2. `[]` The reward position (PDA, may be uninitialized)
+    #[account(0, name="state", desc="The state account")]
+    #[account(1, name="clock", desc="The clock sysvar")]
+    #[account(2, name="token_holder", desc="The token holder account")]
+    #[account(3, name="holder_record", desc="The holder record (PDA, may be uninitialized)")]
+    GetClaimable,
+    
+    /// Pause or resume transfers, distributions and claims in an emergency
+    #[account(0, signer, name="authority", desc="The program authority")]
+    #[account(1, writable, name="state", desc="The state account")]
+    SetPaused {
+        /// Whether the program is paused
+        paused: bool,
+    },
+    
+    /// Publish a Merkle distribution: a root over (index, holder, amount)
+    /// leaves, computed off-chain for the current pool balance. Takes the
+    /// place of a distribution round for holder sets too large to pay
+    /// directly: it follows the same interval and threshold rules, opens a
+    /// new distribution epoch, and moves `total_amount` from the pool
+    /// balance into the reserve, from which holders claim with a proof until
+    /// `MERKLE_CLAIM_PERIOD` has passed. The distribution is recorded in the
+    /// distribution history like a completed round.
+    /// 
+    /// Then, one per `MERKLE_CLAIMS_PER_BITMAP` claims, in order:
+    /// 0. `[writable]` The claim bitmap account (PDA)
+    #[account(0, writable, signer, name="publisher", desc="The program authority or configured Merkle publisher; pays for the distribution and bitmap accounts")]
+    #[account(1, writable, name="state", desc="The state account")]
+    #[account(2, writable, name="merkle_distribution", desc="The Merkle distribution account (PDA)")]
+    #[account(3, name="distribution_round", desc="The distribution round account (PDA, may be uninitialized)")]
+    #[account(4, name="clock", desc="The clock sysvar")]
+    #[account(5, name="system_program", desc="The system program")]
+    #[account(6, writable, name="distribution_history", desc="The distribution history account (PDA, created on first use)")]
+    PublishMerkleRoot {
+        /// Root of the claim tree
+        merkle_root: [u8; 32],
+        
+        /// Lamports owed to all leaves together
+        total_amount: u64,
+        
+        /// Number of leaves in the tree
+        claim_count: u32,
+    },
+    
+    /// Claim a holder's share of a Merkle distribution as SOL
+    #[account(0, writable, signer, name="holder", desc="The holder named in the leaf, which receives the SOL")]
+    #[account(1, writable, name="state", desc="The state account")]
+    #[account(2, writable, name="merkle_distribution", desc="The Merkle distribution account (PDA)")]
+    #[account(3, writable, name="claim_bitmap", desc="The claim bitmap covering `index` (PDA)")]
+    #[account(4, writable, name="dividend_pool", desc="The dividend pool account (PDA)")]
+    #[account(5, name="system_program", desc="The system program")]
+    ClaimMerkleDividend {
+        /// Position of the holder's leaf in the tree
+        index: u32,
+        
+        /// Lamports owed to the holder
+        amount: u64,
+        
+        /// Sibling hashes from the leaf up to the root
+        proof: Vec<[u8; 32]>,
+    },
+    
+    /// Return the unclaimed part of an expired Merkle distribution from the
+    /// reserve to the pool balance
+    #[account(0, signer, name="authority", desc="The program authority")]
+    #[account(1, writable, name="state", desc="The state account")]
+    #[account(2, writable, name="merkle_distribution", desc="The Merkle distribution account (PDA)")]
+    #[account(3, name="clock", desc="The clock sysvar")]
+    ReclaimMerkleDistribution,
+    
+    /// Move a stream's unallocated rewards, which no position can claim, out
+    /// of its vault
+    #[account(0, signer, name="authority", desc="The program authority")]
+    #[account(1, name="state", desc="The state account")]
+    #[account(2, writable, name="reward_stream", desc="The reward stream account (PDA)")]
+    #[account(3, writable, name="reward_vault", desc="The reward vault token account (PDA)")]
+    #[account(4, writable, name="destination", desc="The token account receiving the rewards")]
+    #[account(5, name="token_program", desc="The token program")]
+    SweepRewardStream,
+}
+
+// One size tier of the sell tax schedule. The tier's total tax is the sum
+// of its LP, dividend, burn and treasury shares.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct TaxTier {
+    /// Smallest transfer the tier applies to, in basis points of total supply
+    pub min_amount_bps: u16,
+    
+    /// Share of the transfer sent to the LP pool (basis points)
+    pub lp_bps: u16,
+    
+    /// Share of the transfer sent to the dividend pool (basis points)
+    pub dividend_bps: u16,
+    
+    /// Share of the transfer burned (basis points)
+    pub burn_bps: u16,
+    
+    /// Share of the transfer sent to the treasury vault (basis points)
+    pub treasury_bps: u16,
+}
+
+impl TaxTier {
+    pub const LEN: usize = 2 + 2 + 2 + 2 + 2;
+    
+    pub fn total_bps(&self) -> u16 {
+        self.lp_bps + self.dividend_bps + self.burn_bps + self.treasury_bps
+    }
+    
+    // Split `amount` into the amount delivered and the tax buckets. The total
+    // tax is rounded down once and shared out in proportion to the buckets;
+    // rounding dust goes to the dividend bucket, so nothing is lost.
+    pub fn split(&self, amount: u64) -> TaxSplit {
+        let total_bps = self.total_bps();
+        let total_tax = bps_of(amount, total_bps);
+        if total_tax == 0 {
+            return TaxSplit { transfer_amount: amount, ..TaxSplit::default() };
+        }
+        
+        let share = |bps: u16| (total_tax as u128 * bps as u128 / total_bps as u128) as u64;
+        let lp = share(self.lp_bps);
+        let burn = share(self.burn_bps);
+        let treasury = share(self.treasury_bps);
+        
+        TaxSplit {
+            transfer_amount: amount - total_tax,
+            lp,
+            dividend: total_tax - lp - burn - treasury,
+            burn,
+            treasury,
+        }
+    }
+}
+
+// A transfer split into the amount delivered and its tax buckets.
+// `transfer_amount` plus the buckets always equals the transferred amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TaxSplit {
+    /// Tokens delivered to the destination
+    pub transfer_amount: u64,
+    
+    /// Tokens sent to the LP pool
+    pub lp: u64,
+    
+    /// Tokens sent to the dividend pool and reward streams
+    pub dividend: u64,
+    
+    /// Tokens burned
+    pub burn: u64,
+    
+    /// Tokens sent to the treasury vault
+    pub treasury: u64,
+}
+
+impl TaxSplit {
+    pub fn total_tax(&self) -> u64 {
+        self.lp + self.dividend + self.burn + self.treasury
+    }
+    
+    // Re-split the same total tax for slippage protection mode: the buyback
+    // share goes to the LP pool and the rest to the dividend pool
+    pub fn into_buyback(self) -> TaxSplit {
+        let total_tax = self.total_tax();
+        let buyback = bps_of(total_tax, SLIPPAGE_BUYBACK_BPS);
+        TaxSplit {
+            transfer_amount: self.transfer_amount,
+            lp: buyback,
+            dividend: total_tax - buyback,
+            burn: 0,
+            treasury: 0,
+        }
+    }
+}
+
+// How a holder's dividend multiplier grows with coin age
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum CoinAgeCurve {
+    /// 1.1x after `HOLDING_BONUS_DAYS`, 1x before
+    StepBonus,
+    
+    /// Grows linearly from 1x to `coin_age_max_multiplier_bps` over `coin_age_ramp` seconds
+    Linear,
+    
+    /// Multiplier of the highest `coin_age_tiers` step reached
+    Tiered,
+}
+
+// One step of a tiered coin-age curve
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct CoinAgeTier {
+    /// Coin age, in seconds, at which the step applies
+    pub min_age: i64,
+    
+    /// Dividend weight multiplier (basis points)
+    pub multiplier_bps: u64,
+}
+
+impl CoinAgeTier {
+    pub const LEN: usize = 8 + 8;
+}
+
+// Authority-controlled configuration
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct SolDripConfig {
+    /// Swap adapter program used for compounding, `Pubkey::default()` to disable
+    pub swap_program: Pubkey,
+    
+    /// Maximum holding, in basis points of supply, that compounding may grow
+    /// a holder to; compounded tokens are exempt from the regular maximum
+    /// holding up to this cap
+    pub max_compound_holding_bps: u16,
+    
+    /// Minimum seconds between the starts of two distribution rounds
+    pub min_distribution_interval: i64,
+    
+    /// Seconds after which a round may start even below the distribution
+    /// threshold, so small pools still drain; 0 disables
+    pub force_distribution_after: i64,
+    
+    /// DEX pool token account; transfers out of it are buys
+    pub market_token_account: Pubkey,
+    
+    /// Reject buys until `EnableTrading` has been called
+    pub reject_buys_before_trading: bool,
+    
+    /// Total tax right after trading opens (basis points)
+    pub launch_tax_bps: u16,
+    
+    /// Slots over which the launch tax decays linearly to the standard tax
+    pub launch_tax_slots: u64,
+    
+    /// Maximum tokens per transfer; 0 disables
+    pub max_transaction_amount: u64,
+    
+    /// Minimum seconds between two sells from the same token account; 0 disables
+    pub sell_cooldown: i64,
+    
+    /// Sell tax schedule, in ascending `min_amount_bps` order; only the
+    /// first `tax_tier_count` entries are used
+    pub tax_tiers: [TaxTier; MAX_TAX_TIERS],
+    
+    /// Number of tiers in use
+    pub tax_tier_count: u8,
+    
+    /// Wallet allowed to withdraw from the treasury vault, `Pubkey::default()` for nobody
+    pub treasury_authority: Pubkey,
+    
+    /// Maximum tokens withdrawn from the treasury per period. Must be set
+    /// before `InitializeTreasury`; 0 blocks withdrawals.
+    pub treasury_withdraw_limit: u64,
+    
+    /// Length of a treasury withdrawal period in seconds
+    pub treasury_withdraw_period: i64,
+    
+    /// Curve mapping coin age to a dividend weight multiplier
+    pub coin_age_curve: CoinAgeCurve,
+    
+    /// Multiplier cap of the linear curve (basis points)
+    pub coin_age_max_multiplier_bps: u64,
+    
+    /// Seconds of coin age for the linear curve to reach its cap
+    pub coin_age_ramp: i64,
+    
+    /// Steps of the tiered curve, in ascending `min_age` order; only the
+    /// first `coin_age_tier_count` entries are used
+    pub coin_age_tiers: [CoinAgeTier; MAX_COIN_AGE_TIERS],
+    
+    /// Number of tiered curve steps in use
+    pub coin_age_tier_count: u8,
+    
+    /// Pay dividends only on the balance held across the whole accrual
+    /// period, as tracked in holder records
+    pub snapshot_eligibility: bool,
+    
+    /// Wallet allowed to publish Merkle distributions besides the authority,
+    /// `Pubkey::default()` for the authority only
+    pub merkle_publisher: Pubkey,
+    
+    /// Wallet allowed to convert reward stream tax besides the authority,
+    /// `Pubkey::default()` for the authority only
+    pub stream_converter: Pubkey,
+}
+
+impl SolDripConfig {
+    pub const LEN: usize = 32 + 2 + 8 + 8 + 32 + 1 + 2 + 8 + 8 + 8 + TaxTier::LEN * MAX_TAX_TIERS + 1 + 32 + 8 + 8
+        + 1 + 8 + 8 + CoinAgeTier::LEN * MAX_COIN_AGE_TIERS + 1 + 1 + 32 + 32;
+    
+    // Reject configurations the transfer and distribution paths cannot apply
+    pub fn validate(&self) -> Result<(), SolDripError> {
+        if self.launch_tax_bps as u64 > BPS_DENOMINATOR
+            || self.treasury_withdraw_period <= 0
+            || self.min_distribution_interval < 0
+        {
+            return Err(SolDripError::InvalidConfig);
+        }
+        
+        let count = self.tax_tier_count as usize;
+        if count == 0 || count > MAX_TAX_TIERS || self.tax_tiers[0].min_amount_bps != 0 {
+            return Err(SolDripError::InvalidTaxTiers);
+        }
+        
+        let tiers = &self.tax_tiers[..count];
+        if tiers.windows(2).any(|pair| pair[0].min_amount_bps >= pair[1].min_amount_bps) {
+            return Err(SolDripError::InvalidTaxTiers);
+        }
+        let tier_total = |tier: &TaxTier| tier.lp_bps as u32 + tier.dividend_bps as u32 + tier.burn_bps as u32 + tier.treasury_bps as u32;
+        if tiers.iter().any(|tier| tier_total(tier) > MAX_TIER_TAX_BPS as u32) {
+            return Err(SolDripError::InvalidTaxTiers);
+        }
+        
+        let multiplier_in_range = |bps: u64| (BASE_MULTIPLIER_BPS..=MAX_COIN_AGE_MULTIPLIER_BPS).contains(&bps);
+        match self.coin_age_curve {
+            CoinAgeCurve::StepBonus => {},
+            CoinAgeCurve::Linear => {
+                if self.coin_age_ramp <= 0 || !multiplier_in_range(self.coin_age_max_multiplier_bps) {
+                    return Err(SolDripError::InvalidCoinAgeCurve);
+                }
+            },
+            CoinAgeCurve::Tiered => {
+                let count = self.coin_age_tier_count as usize;
+                if count == 0 || count > MAX_COIN_AGE_TIERS {
+                    return Err(SolDripError::InvalidCoinAgeCurve);
+                }
+                let steps = &self.coin_age_tiers[..count];
+                if steps.windows(2).any(|pair| pair[0].min_age >= pair[1].min_age)
+                    || steps.iter().any(|step| !multiplier_in_range(step.multiplier_bps))
+                {
+                    return Err(SolDripError::InvalidCoinAgeCurve);
+                }
+            },
+        }
+        
+        Ok(())
+    }
+    
+    // Dividend weight multiplier for a holding of the given coin age in
+    // seconds, `None` for holders without a holder record
+    pub fn holding_multiplier_bps(&self, coin_age: Option<i64>) -> u64 {
+        let coin_age = match coin_age {
+            Some(coin_age) => coin_age.max(0),
+            None => return BASE_MULTIPLIER_BPS,
+        };
+        
+        match self.coin_age_curve {
+            // Apply 1.1x bonus for holders of 7+ days
+            CoinAgeCurve::StepBonus if coin_age / SECONDS_PER_DAY >= HOLDING_BONUS_DAYS as i64 => HOLDING_BONUS_MULTIPLIER_BPS,
+            CoinAgeCurve::StepBonus => BASE_MULTIPLIER_BPS,
+            CoinAgeCurve::Linear => {
+                let bonus = self.coin_age_max_multiplier_bps.saturating_sub(BASE_MULTIPLIER_BPS);
+                let ramp = self.coin_age_ramp.max(1);
+                BASE_MULTIPLIER_BPS + (bonus as u128 * coin_age.min(ramp) as u128 / ramp as u128) as u64
+            },
+            CoinAgeCurve::Tiered => self.coin_age_tiers[..self.coin_age_tier_count as usize]
+                .iter()
+                .rev()
+                .find(|step| coin_age >= step.min_age)
+                .map_or(BASE_MULTIPLIER_BPS, |step| step.multiplier_bps),
+        }
+    }
+    
+    // Highest multiplier any holder can reach under the current curve
+    pub fn max_holding_multiplier_bps(&self) -> u64 {
+        match self.coin_age_curve {
+            CoinAgeCurve::StepBonus => HOLDING_BONUS_MULTIPLIER_BPS,
+            CoinAgeCurve::Linear => self.coin_age_max_multiplier_bps.max(BASE_MULTIPLIER_BPS),
+            CoinAgeCurve::Tiered => self.coin_age_tiers[..self.coin_age_tier_count as usize]
+                .iter()
+                .map(|step| step.multiplier_bps)
+                .fold(BASE_MULTIPLIER_BPS, u64::max),
+        }
+    }
+    
+    // Index of the highest tier whose threshold `amount` reaches
+    pub fn tax_tier_index(&self, amount: u64, total_supply: u64) -> usize {
+        self.tax_tiers[..self.tax_tier_count as usize]
+            .iter()
+            .rposition(|tier| amount as u128 * BPS_DENOMINATOR as u128 >= total_supply as u128 * tier.min_amount_bps as u128)
+            .unwrap_or(0)
+    }
+    
+    // The highest tier whose threshold `amount` reaches
+    pub fn tax_tier(&self, amount: u64, total_supply: u64) -> TaxTier {
+        self.tax_tiers[self.tax_tier_index(amount, total_supply)]
+    }
+    
+    // The next multiplier increase for a holding of the given coin age:
+    // the coin age it is reached at and the multiplier from then on
+    pub fn next_multiplier_milestone(&self, coin_age: i64) -> Option<(i64, u64)> {
+        let coin_age = coin_age.max(0);
+        match self.coin_age_curve {
+            CoinAgeCurve::StepBonus => {
+                let bonus_age = HOLDING_BONUS_DAYS as i64 * SECONDS_PER_DAY;
+                (coin_age < bonus_age).then_some((bonus_age, HOLDING_BONUS_MULTIPLIER_BPS))
+            },
+            // The linear curve rises continuously; its milestone is the end of the ramp
+            CoinAgeCurve::Linear => (coin_age < self.coin_age_ramp && self.coin_age_max_multiplier_bps > BASE_MULTIPLIER_BPS)
+                .then_some((self.coin_age_ramp, self.coin_age_max_multiplier_bps)),
+            CoinAgeCurve::Tiered => {
+                let current = self.holding_multiplier_bps(Some(coin_age));
+                self.coin_age_tiers[..self.coin_age_tier_count as usize]
+                    .iter()
+                    .find(|step| step.min_age > coin_age && step.multiplier_bps > current)
+                    .map(|step| (step.min_age, step.multiplier_bps))
+            },
+        }
+    }
+    
+    // Launch tax at `slot`, or `None` once it has decayed to the standard tax
+    pub fn launch_tax_bps(&self, launch_slot: u64, slot: u64) -> Option<u16> {
+        let elapsed = slot.saturating_sub(launch_slot);
+        if elapsed >= self.launch_tax_slots || self.launch_tax_bps <= TAX_BPS {
+            return None;
+        }
+        
+        let excess = (self.launch_tax_bps - TAX_BPS) as u64;
+        let remaining = excess * (self.launch_tax_slots - elapsed) / self.launch_tax_slots;
+        Some(TAX_BPS + remaining as u16)
+    }
+    
+    // Whether a new distribution round may start
+    pub fn distribution_due(&self, pool_lamports: u64, last_distribution_timestamp: i64, now: i64) -> Result<(), SolDripError> {
+        if pool_lamports == 0 {
+            return Err(SolDripError::InsufficientSolForDistribution);
+        }
+        
+        let elapsed = now.saturating_sub(last_distribution_timestamp);
+        if elapsed < self.min_distribution_interval {
+            return Err(SolDripError::DistributionTooSoon);
+        }
+        
+        let forced = self.force_distribution_after > 0 && elapsed >= self.force_distribution_after;
+        if pool_lamports < DISTRIBUTION_THRESHOLD && !forced {
+            return Err(SolDripError::InsufficientSolForDistribution);
+        }
+        
+        Ok(())
+    }
+}
+
+impl Default for SolDripConfig {
+    fn default() -> Self {
+        SolDripConfig {
+            swap_program: Pubkey::default(),
+            max_compound_holding_bps: DEFAULT_MAX_COMPOUND_HOLDING_BPS,
+            min_distribution_interval: DEFAULT_MIN_DISTRIBUTION_INTERVAL,
+            force_distribution_after: DEFAULT_FORCE_DISTRIBUTION_AFTER,
+            market_token_account: Pubkey::default(),
+            reject_buys_before_trading: true,
+            launch_tax_bps: DEFAULT_LAUNCH_TAX_BPS,
+            launch_tax_slots: DEFAULT_LAUNCH_TAX_SLOTS,
+            max_transaction_amount: 0,
+            sell_cooldown: DEFAULT_SELL_COOLDOWN,
+            tax_tiers: [
+                TaxTier { min_amount_bps: 0, lp_bps: LP_TAX_BPS, dividend_bps: DIVIDEND_TAX_BPS, burn_bps: 0, treasury_bps: 0 },
+                TaxTier { min_amount_bps: 5, lp_bps: LP_TAX_BPS, dividend_bps: 500, burn_bps: 0, treasury_bps: 0 },
+                TaxTier { min_amount_bps: 10, lp_bps: LP_TAX_BPS, dividend_bps: 500, burn_bps: 100, treasury_bps: 0 },
+                TaxTier { min_amount_bps: LARGE_SALE_THRESHOLD_BPS, lp_bps: LP_TAX_BPS, dividend_bps: HIGH_DIVIDEND_TAX_BPS, burn_bps: 0, treasury_bps: 0 },
+                TaxTier { min_amount_bps: 50, lp_bps: LP_TAX_BPS, dividend_bps: 800, burn_bps: 100, treasury_bps: 0 },
+            ],
+            tax_tier_count: MAX_TAX_TIERS as u8,
+            treasury_authority: Pubkey::default(),
+            treasury_withdraw_limit: 0,
+            treasury_withdraw_period: DEFAULT_TREASURY_WITHDRAW_PERIOD,
+            coin_age_curve: CoinAgeCurve::StepBonus,
+            coin_age_max_multiplier_bps: 15_000,
+            coin_age_ramp: 30 * SECONDS_PER_DAY,
+            coin_age_tiers: [CoinAgeTier::default(); MAX_COIN_AGE_TIERS],
+            coin_age_tier_count: 0,
+            snapshot_eligibility: true,
+            merkle_publisher: Pubkey::default(),
+            stream_converter: Pubkey::default(),
+        }
+    }
 }
 
 // Program state
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, ShankAccount)]
 pub struct SolDripState {
     /// The mint of the token
     pub mint: Pubkey,
@@ -128,7 +1019,7 @@ pub struct SolDripState {
     /// The dividend pool account
     pub dividend_pool: Pubkey,
     
-    /// The LP pool account
+    /// The LP pool token account, held by the dividend pool
     pub lp_pool: Pubkey,
     
     /// Total SOL distributed as dividends
@@ -148,477 +1039,5539 @@ pub struct SolDripState {
     
     /// Total supply
     pub total_supply: u64,
-}
-
-// Process instruction entrypoint
-pub fn process_instruction(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    instruction_data: &[u8],
-) -> ProgramResult {
-    // Deserialize instruction
-    let instruction = SolDripInstruction::try_from_slice(instruction_data)
-        .map_err(|_| SolDripError::InvalidInstruction)?;
     
-    // Process the instruction
-    match instruction {
-        SolDripInstruction::Initialize { total_supply } => {
-            msg!("Instruction: Initialize");
-            process_initialize(program_id, accounts, total_supply)
-        },
-        SolDripInstruction::TransferWithTax { amount } => {
-            msg!("Instruction: TransferWithTax");
-            process_transfer_with_tax(program_id, accounts, amount)
-        },
-        SolDripInstruction::DistributeDividends => {
-            msg!("Instruction: DistributeDividends");
-            process_distribute_dividends(program_id, accounts)
-        },
-    }
-}
-
-// Initialize a new SolDrip token
-fn process_initialize(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    total_supply: u64,
-) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
+    /// Dividend pool PDA bump seed
+    pub dividend_pool_bump: u8,
     
-    // Get accounts
-    let initializer = next_account_info(account_info_iter)?;
-    let mint_account = next_account_info(account_info_iter)?;
-    let dividend_pool = next_account_info(account_info_iter)?;
-    let lp_pool = next_account_info(account_info_iter)?;
-    let state_account = next_account_info(account_info_iter)?;
-    let rent_account = next_account_info(account_info_iter)?;
-    let token_program = next_account_info(account_info_iter)?;
+    /// Authority allowed to update the configuration
+    pub authority: Pubkey,
     
-    // Verify signer
-    if !initializer.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
+    /// Lamports in the dividend pool owed to holders but not yet claimed
+    pub reserved_lamports: u64,
     
-    // Create mint account
-    let rent = &Rent::from_account_info(rent_account)?;
-    let mint_space = Mint::LEN;
-    let mint_rent = rent.minimum_balance(mint_space);
+    /// Distributable lamports in the dividend pool, credited only through
+    /// `DepositDividends` and `SyncPool`; never includes the rent reserve
+    pub pool_balance: u64,
+    
+    /// Lamports committed to the active distribution round and not yet paid
+    pub round_lamports: u64,
+    
+    /// Total lamports booked as donations by `SyncPool`
+    pub total_donations: u64,
+    
+    /// Authority-controlled configuration
+    pub config: SolDripConfig,
+    
+    /// Number of SPL reward streams
+    pub reward_stream_count: u8,
+    
+    /// Sum of the reward streams' shares of the dividend tax (basis points)
+    pub reward_stream_tax_share_bps: u16,
+    
+    /// Has trading been enabled
+    pub trading_enabled: bool,
+    
+    /// Slot at which trading was enabled
+    pub launch_slot: u64,
+    
+    /// Total tokens burned by the tax
+    pub total_burned: u64,
+    
+    /// Treasury vault, `Pubkey::default()` until `InitializeTreasury`
+    pub treasury_vault: Pubkey,
+    
+    /// Start of the current treasury withdrawal period
+    pub treasury_period_start: i64,
+    
+    /// Tokens withdrawn from the treasury in the current period
+    pub treasury_withdrawn_in_period: u64,
+    
+    /// Total tokens withdrawn from the treasury
+    pub total_treasury_withdrawn: u64,
+    
+    /// Current accrual period, advanced when a distribution round starts;
+    /// round N pays for the balances held during epoch N - 1
+    pub distribution_epoch: u64,
+    
+    /// Transfers, distributions and claims are halted
+    pub paused: bool,
+    
+    /// Number of Merkle distributions published
+    pub merkle_distribution_count: u64,
+    
+    /// Sum of the dividend weights counted by all holder records; the
+    /// denominator of holder shares
+    pub total_weight: u128,
+}
+
+impl SolDripState {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 8 + 2 + 1 + 8 + 8 + 1 + 32 + 8 + 8 + 8 + 8 + SolDripConfig::LEN + 1 + 2 + 1 + 8 + 8 + 32 + 8 + 8 + 8 + 8 + 1 + 8 + 16;
+    
+    // The dividend pool's associated token account, which collects the
+    // dividend tax in tokens
+    pub fn dividend_tax_account(&self) -> Pubkey {
+        get_associated_token_address(&self.dividend_pool, &self.mint)
+    }
+    
+    // Supply left after tax burns
+    pub fn circulating_supply(&self) -> u64 {
+        self.total_supply.saturating_sub(self.total_burned)
+    }
+    
+    // Lamports in the dividend pool accounted for by the state: the
+    // distributable balance, the active round and unclaimed accruals
+    pub fn booked_pool_lamports(&self) -> u64 {
+        self.pool_balance + self.round_lamports + self.reserved_lamports
+    }
+    
+    // Transfers out of the DEX pool token account are buys
+    pub fn is_buy(&self, source: &Pubkey) -> bool {
+        self.config.market_token_account != Pubkey::default() && *source == self.config.market_token_account
+    }
+    
+    // Transfers into the DEX pool token account are sells
+    pub fn is_sell(&self, destination: &Pubkey) -> bool {
+        self.config.market_token_account != Pubkey::default() && *destination == self.config.market_token_account
+    }
+    
+    // Whether slippage protection still blocks transfers at `now`
+    pub fn slippage_protection_blocks(&self, now: i64) -> bool {
+        self.slippage_protection_active && now - self.slippage_protection_timestamp < SLIPPAGE_PROTECTION_DURATION
+    }
+    
+    // Most tokens a compounding claim may leave a holder with: the regular
+    // maximum holding, or the compound cap if it is higher
+    pub fn compound_holding_limit(&self) -> u64 {
+        let max_holding = bps_of(self.total_supply, MAX_HOLDING_BPS);
+        let compound_cap = bps_of(self.total_supply, self.config.max_compound_holding_bps);
+        max_holding.max(compound_cap)
+    }
+    
+    // Token accounts of the program and the DEX that never earn dividends
+    pub fn dividend_exclusions(&self) -> impl Iterator<Item = Pubkey> {
+        let dividend_tax_account = match self.dividend_pool == Pubkey::default() {
+            true => Pubkey::default(),
+            false => self.dividend_tax_account(),
+        };
+        [self.config.market_token_account, self.lp_pool, self.treasury_vault, dividend_tax_account]
+            .into_iter()
+            .filter(|address| *address != Pubkey::default())
+    }
+    
+    // Whether `token_account` may hold a holder record and earn dividends.
+    // Reward stream token accounts are checked with `RewardStream::holds`.
+    pub fn earns_dividends(&self, token_account: &Pubkey) -> bool {
+        !self.dividend_exclusions().any(|excluded| excluded == *token_account)
+    }
+    
+    // Price fluctuation is high enough to split the tax for buybacks
+    pub fn buyback_mode(&self) -> bool {
+        self.price_fluctuation_bps > PRICE_FLUCTUATION_THRESHOLD_BPS
+    }
+    
+    // Every transfer rule `transfer` breaks at `now`. `TransferWithTax`
+    // rejects the first one; `QuoteTransfer` reports them all.
+    pub fn transfer_violations(&self, transfer: &TransferCheck, now: i64) -> TransferViolations {
+        let limited = !transfer.exempt_from_limits;
+        TransferViolations {
+            paused: self.paused,
+            insufficient_balance: transfer.source_balance < transfer.amount,
+            trading_not_enabled: self.is_buy(&transfer.source)
+                && !self.trading_enabled
+                && self.config.reject_buys_before_trading,
+            exceeds_max_transaction: limited
+                && self.config.max_transaction_amount > 0
+                && transfer.amount > self.config.max_transaction_amount,
+            sell_cooldown_active: limited
+                && self.is_sell(&transfer.destination)
+                && self.config.sell_cooldown > 0
+                && transfer.last_sell_timestamp.is_some_and(|last_sell| now - last_sell < self.config.sell_cooldown),
+            // The DEX pool holds whatever is sold into it
+            exceeds_max_holding: !self.is_sell(&transfer.destination)
+                && transfer.destination_balance.saturating_add(transfer.split.transfer_amount)
+                    > bps_of(self.total_supply, MAX_HOLDING_BPS),
+            slippage_protection_active: self.slippage_protection_blocks(now),
+            treasury_not_initialized: transfer.split.treasury > 0 && self.treasury_vault == Pubkey::default(),
+        }
+    }
+    
+    // Whether `publisher` may publish Merkle distributions
+    pub fn can_publish_merkle_root(&self, publisher: &Pubkey) -> bool {
+        *publisher == self.authority
+            || (self.config.merkle_publisher != Pubkey::default() && *publisher == self.config.merkle_publisher)
+    }
+    
+    pub fn can_convert_stream_tax(&self, converter: &Pubkey) -> bool {
+        *converter == self.authority
+            || (self.config.stream_converter != Pubkey::default() && *converter == self.config.stream_converter)
+    }
+    
+    // Count a treasury withdrawal of `amount` at `now` against the per-period
+    // limit, starting a new period once the current one has elapsed
+    pub fn record_treasury_withdrawal(&mut self, amount: u64, now: i64) -> Result<(), SolDripError> {
+        let (period_start, withdrawn_in_period) = match now - self.treasury_period_start >= self.config.treasury_withdraw_period {
+            true => (now, 0),
+            false => (self.treasury_period_start, self.treasury_withdrawn_in_period),
+        };
+        let withdrawn = withdrawn_in_period
+            .checked_add(amount)
+            .filter(|withdrawn| *withdrawn <= self.config.treasury_withdraw_limit)
+            .ok_or(SolDripError::TreasuryWithdrawalLimitExceeded)?;
+        
+        self.treasury_period_start = period_start;
+        self.treasury_withdrawn_in_period = withdrawn;
+        self.total_treasury_withdrawn += amount;
+        Ok(())
+    }
+    
+    // Tax tier charged on a transfer of `amount` at `slot`, and the index of
+    // its size tier. While the launch tax is higher than the size tier's
+    // tax, the excess goes to the dividend pool.
+    pub fn transfer_tax_tier(&self, amount: u64, slot: u64) -> (usize, TaxTier) {
+        let index = self.config.tax_tier_index(amount, self.total_supply);
+        let mut tier = self.config.tax_tiers[index];
+        
+        if self.trading_enabled {
+            if let Some(launch_tax_bps) = self.config.launch_tax_bps(self.launch_slot, slot) {
+                if launch_tax_bps > tier.total_bps() {
+                    tier.dividend_bps = launch_tax_bps - tier.lp_bps - tier.burn_bps - tier.treasury_bps;
+                }
+            }
+        }
+        
+        (index, tier)
+    }
+}
+
+// SPL token reward stream, one PDA per reward mint
+#[derive(BorshSerialize, BorshDeserialize, Debug, ShankAccount)]
+pub struct RewardStream {
+    /// The state account this stream belongs to
+    pub state: Pubkey,
+    
+    /// The reward token mint
+    pub reward_mint: Pubkey,
+    
+    /// Token account holding undistributed reward tokens
+    pub vault: Pubkey,
+    
+    /// Token account collecting this stream's share of the DRIP dividend tax
+    pub tax_account: Pubkey,
+    
+    /// Share of the dividend tax routed to this stream (basis points)
+    pub tax_share_bps: u16,
+    
+    /// Reward tokens credited per DRIP token, scaled by `REWARD_PRECISION`
+    pub reward_per_token: u128,
+    
+    /// Total reward tokens credited to holders
+    pub total_funded: u64,
+    
+    /// Total reward tokens claimed by holders
+    pub total_claimed: u64,
+    
+    /// PDA bump seed
+    pub bump: u8,
+    
+    /// Sum of the balances open positions earn on
+    pub total_position_balance: u64,
+    
+    /// Reward tokens credited that no position can claim, until swept
+    pub unallocated: u64,
+}
+
+impl RewardStream {
+    pub const LEN: usize = 32 + 32 + 32 + 32 + 2 + 16 + 8 + 8 + 1 + 8 + 8;
+    
+    // Whether `token_account` is the stream's vault or tax account
+    pub fn holds(&self, token_account: &Pubkey) -> bool {
+        self.vault == *token_account || self.tax_account == *token_account
+    }
+    
+    // Credit newly deposited reward tokens to the balances of open
+    // positions. Tokens without a position never earn, so they dilute no
+    // one; with no position open the deposit is left unallocated.
+    fn accrue(&mut self, amount: u64) {
+        match self.total_position_balance {
+            0 => self.unallocated += amount,
+            total => self.reward_per_token += amount as u128 * REWARD_PRECISION / total as u128,
+        }
+        self.total_funded += amount;
+    }
+}
+
+// A token account's position in a reward stream
+#[derive(BorshSerialize, BorshDeserialize, Debug, ShankAccount)]
+pub struct RewardPosition {
+    /// The reward stream
+    pub stream: Pubkey,
+    
+    /// The token account earning rewards
+    pub token_account: Pubkey,
+    
+    /// Stream `reward_per_token` at the last settlement
+    pub reward_per_token_paid: u128,
+    
+    /// Token balance earning rewards since the last settlement
+    pub balance: u64,
+    
+    /// Rewards settled but not yet claimed
+    pub accrued: u64,
+    
+    /// Total reward tokens claimed
+    pub total_claimed: u64,
+    
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl RewardPosition {
+    pub const LEN: usize = 32 + 32 + 16 + 8 + 8 + 8 + 1;
+    
+    // Rewards owed, given the account's `live_balance`. Positions are settled
+    // on every balance change through the program; the settled balance is
+    // capped at the live one in case tokens left the account since.
+    pub fn pending(&self, stream: &RewardStream, live_balance: u64) -> u64 {
+        let delta = stream.reward_per_token.saturating_sub(self.reward_per_token_paid);
+        let balance = self.balance.min(live_balance);
+        self.accrued.saturating_add((balance as u128 * delta / REWARD_PRECISION) as u64)
+    }
+    
+    // Bank the rewards owed so far, then earn on `new_balance` from here on.
+    // Rewards credited to tokens that have since left the account without
+    // being settled are owed to no one, and become unallocated.
+    pub fn settle(&mut self, stream: &mut RewardStream, live_balance: u64, new_balance: u64) {
+        let accrued = self.pending(stream, live_balance);
+        stream.unallocated += self.pending(stream, u64::MAX) - accrued;
+        stream.total_position_balance = stream.total_position_balance.saturating_sub(self.balance) + new_balance;
+        self.accrued = accrued;
+        self.reward_per_token_paid = stream.reward_per_token;
+        self.balance = new_balance;
+    }
+    
+    // Settle and take up to `available` of the rewards owed
+    pub fn claim(&mut self, stream: &mut RewardStream, live_balance: u64, available: u64) -> u64 {
+        self.settle(stream, live_balance, live_balance);
+        let amount = self.accrued.min(available);
+        self.accrued -= amount;
+        self.total_claimed += amount;
+        amount
+    }
+}
+
+// Per token account holder record
+#[derive(BorshSerialize, BorshDeserialize, Debug, ShankAccount)]
+pub struct HolderRecord {
+    /// The state account this record belongs to
+    pub state: Pubkey,
+    
+    /// The token account this record tracks
+    pub token_account: Pubkey,
+    
+    /// Wallet that receives dividends, `Pubkey::default()` for the token account owner
+    pub payout_address: Pubkey,
+    
+    /// PDA bump seed
+    pub bump: u8,
+    
+    /// Reinvest dividends into DRIP instead of paying SOL
+    pub compound: bool,
+    
+    /// Dividends credited to this holder but not yet claimed
+    pub accrued_lamports: u64,
+    
+    /// Exempt from the maximum transaction amount and sell cooldown
+    pub exempt_from_limits: bool,
+    
+    /// Coin-age anchor: the holding's coin age is `now - holding_since`
+    pub holding_since: i64,
+    
+    /// Distribution epoch the balance snapshot below belongs to
+    pub balance_epoch: u64,
+    
+    /// Lowest balance held during `balance_epoch`
+    pub epoch_min_balance: u64,
+    
+    /// Lowest balance held during `balance_epoch - 1`
+    pub previous_epoch_min_balance: u64,
+    
+    /// Balance counted in the state's total dividend weight
+    pub weight_balance: u64,
+    
+    /// Coin-age multiplier the counted balance is weighted at (basis points)
+    pub weight_multiplier_bps: u64,
+    
+    /// Last distribution round that paid this record
+    pub paid_round: u64,
+    
+    /// Distribution epoch `epoch_start_weight` was taken in
+    pub weight_epoch: u64,
+    
+    /// Dividend weight counted when distribution epoch `weight_epoch` opened
+    pub epoch_start_weight: u128,
+}
+
+impl HolderRecord {
+    pub const LEN: usize = 32 + 32 + 32 + 1 + 1 + 8 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 16;
+    
+    // Record a balance change made in the state's current epoch, and count
+    // the new balance at the holder's current coin age in the state's total
+    // weight. Coin-age adjustments for the change must already be applied.
+    // Before the first change of a new epoch the balance is taken as
+    // unchanged since the record's last update, so `balance_before` is also
+    // the balance the new epoch started with. Changes the program never sees,
+    // such as DEX swaps and plain SPL transfers, are only observed when a
+    // distribution passes the record, with `balance_before` equal to
+    // `balance_after`; until then they neither count nor earn.
+    pub fn track_balance(&mut self, state: &mut SolDripState, balance_before: u64, balance_after: u64, now: i64) {
+        let epoch = state.distribution_epoch;
+        if self.balance_epoch != epoch {
+            self.previous_epoch_min_balance = if self.balance_epoch + 1 == epoch {
+                self.epoch_min_balance
+            } else {
+                balance_before
+            };
+            self.epoch_min_balance = balance_before;
+            self.balance_epoch = epoch;
+        }
+        self.epoch_min_balance = self.epoch_min_balance.min(balance_after);
+        
+        let multiplier_bps = state.config.holding_multiplier_bps(Some(self.coin_age(now)));
+        self.reweigh(state, balance_after, multiplier_bps);
+    }
+    
+    // Flag the record as paid by `round_id`, rejecting a second payment
+    pub fn mark_paid(&mut self, round_id: u64) -> Result<(), SolDripError> {
+        if self.paid_round == round_id {
+            return Err(SolDripError::HolderAlreadyPaid);
+        }
+        self.paid_round = round_id;
+        Ok(())
+    }
+    
+    // Dividend weight this record adds to the state's total weight
+    pub fn counted_weight(&self) -> u128 {
+        dividend_weight(self.weight_balance, self.weight_multiplier_bps)
+    }
+    
+    // Dividend weight the record counted when distribution epoch `epoch`
+    // opened, which is its share of the total weight a round starting that
+    // epoch snapshots
+    pub fn start_weight(&self, epoch: u64) -> u128 {
+        match self.weight_epoch == epoch {
+            true => self.epoch_start_weight,
+            false => self.counted_weight(),
+        }
+    }
+    
+    // Count `balance` at `multiplier_bps` in the state's total weight in
+    // place of the record's current contribution, keeping the weight the
+    // current epoch opened with
+    pub fn reweigh(&mut self, state: &mut SolDripState, balance: u64, multiplier_bps: u64) {
+        if self.weight_epoch != state.distribution_epoch {
+            self.epoch_start_weight = self.counted_weight();
+            self.weight_epoch = state.distribution_epoch;
+        }
+        state.total_weight = state.total_weight.saturating_sub(self.counted_weight());
+        self.weight_balance = balance;
+        self.weight_multiplier_bps = multiplier_bps;
+        state.total_weight += self.counted_weight();
+    }
+    
+    // Balance and dividend weight the round that started `epoch` pays the
+    // record on: its eligible balance, or its live balance without snapshot
+    // eligibility, at the counted multiplier. Capped at the counted balance
+    // and at the weight the record counted when the round started, so the
+    // weight never exceeds what the record added to the round's total weight.
+    pub fn payout_weight(&self, config: &SolDripConfig, epoch: u64, live_balance: u64) -> (u64, u128) {
+        let balance = if config.snapshot_eligibility {
+            self.eligible_balance(epoch, live_balance)
+        } else {
+            live_balance
+        }
+        .min(self.weight_balance);
+        (balance, dividend_weight(balance, self.weight_multiplier_bps).min(self.start_weight(epoch)))
+    }
+    
+    // Balance held across the whole accrual period paid for by the round
+    // that started `epoch`, never more than the live balance
+    pub fn eligible_balance(&self, epoch: u64, live_balance: u64) -> u64 {
+        let held = if self.balance_epoch == epoch {
+            self.previous_epoch_min_balance
+        } else if self.balance_epoch + 1 == epoch {
+            self.epoch_min_balance
+        } else {
+            live_balance
+        };
+        held.min(live_balance)
+    }
+    
+    // Coin age of the holding in seconds
+    pub fn coin_age(&self, now: i64) -> i64 {
+        now.saturating_sub(self.holding_since).max(0)
+    }
+    
+    // Scale the coin age down by the share of `balance` being sent away
+    pub fn reset_coin_age_on_sell(&mut self, balance: u64, amount: u64, now: i64) {
+        if balance == 0 {
+            return;
+        }
+        let kept = balance.saturating_sub(amount);
+        let age = (self.coin_age(now) as u128 * kept as u128 / balance as u128) as i64;
+        self.holding_since = now - age;
+    }
+    
+    // Blend in newly received tokens at zero coin age
+    pub fn dilute_coin_age_on_receive(&mut self, balance: u64, amount: u64, now: i64) {
+        let total = balance as u128 + amount as u128;
+        if total == 0 {
+            return;
+        }
+        let age = (self.coin_age(now) as u128 * balance as u128 / total) as i64;
+        self.holding_since = now - age;
+    }
+    
+    // Resolve the dividend recipient for this holder
+    pub fn payout_recipient(&self, token_owner: &Pubkey) -> Pubkey {
+        if self.payout_address == Pubkey::default() {
+            *token_owner
+        } else {
+            self.payout_address
+        }
+    }
+    
+    // Recipient a distributor passes for this record in `DistributeDividends`,
+    // given the owner and balance of its token account if it still exists, or
+    // `None` if passing the record would change nothing. A closed token
+    // account's recipient is not used.
+    pub fn distribution_recipient(&self, token: Option<(Pubkey, u64)>) -> Option<Pubkey> {
+        match token {
+            Some((owner, amount)) if amount > 0 || self.weight_balance > 0 => Some(self.payout_recipient(&owner)),
+            None if self.weight_balance > 0 => Some(self.token_account),
+            _ => None,
+        }
+    }
+}
+
+// Per wallet sell record. The sell cooldown is tracked for the owner of the
+// selling token account, so moving tokens to another account does not reset it.
+#[derive(BorshSerialize, BorshDeserialize, Debug, ShankAccount)]
+pub struct SellerRecord {
+    /// The state account this record belongs to
+    pub state: Pubkey,
+    
+    /// The wallet whose sells this record tracks
+    pub owner: Pubkey,
+    
+    /// Timestamp of the last sell to the DEX pool
+    pub last_sell_timestamp: i64,
+    
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl SellerRecord {
+    pub const LEN: usize = 32 + 32 + 8 + 1;
+    
+    // Check the sell cooldown for a sell at `now`, and start a new one
+    pub fn record_sell(&mut self, sell_cooldown: i64, now: i64) -> Result<(), SolDripError> {
+        if sell_cooldown > 0 && now - self.last_sell_timestamp < sell_cooldown {
+            return Err(SolDripError::SellCooldownActive);
+        }
+        self.last_sell_timestamp = now;
+        Ok(())
+    }
+}
+
+// Distribution round state, one PDA per SolDrip state account
+#[derive(BorshSerialize, BorshDeserialize, Debug, ShankAccount)]
+pub struct DistributionRound {
+    /// The state account this round belongs to
+    pub state: Pubkey,
+    
+    /// Sequential round number, starting at 1
+    pub round_id: u64,
+    
+    /// Is the round accepting continuation calls
+    pub is_active: bool,
+    
+    /// Lamports available to this round, snapshotted at round start
+    pub distributable_lamports: u64,
+    
+    /// Circulating token supply, snapshotted at round start
+    pub total_supply: u64,
+    
+    /// Share denominator: the state's total dividend weight, snapshotted at round start
+    pub total_weight: u128,
+    
+    /// Dividend weight paid so far in this round, never above `total_weight`
+    pub distributed_weight: u128,
+    
+    /// Lamports paid out so far in this round
+    pub distributed_lamports: u64,
+    
+    /// Gas reserve withheld from the pool to reward distribution cranks
+    pub crank_reward_lamports: u64,
+    
+    /// Crank rewards paid out so far in this round
+    pub crank_rewards_paid: u64,
+    
+    /// Number of token holders paid in this round
+    pub holders_paid: u32,
+    
+    /// Sum of the eligible balances paid in this round
+    pub eligible_supply: u64,
+    
+    /// Round start timestamp
+    pub started_at: i64,
+    
+    /// The account that started the round
+    pub started_by: Pubkey,
+    
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl DistributionRound {
+    pub const LEN: usize = 32 + 8 + 1 + 8 + 8 + 16 + 16 + 8 + 8 + 8 + 4 + 8 + 8 + 32 + 1;
+    
+    // Crank reward for a call that paid `holders_paid` holders: a flat
+    // amount per holder, up to what is left of the gas reserve
+    pub fn crank_reward(&self, holders_paid: u32) -> u64 {
+        (holders_paid as u64)
+            .saturating_mul(CRANK_REWARD_PER_HOLDER)
+            .min(self.crank_reward_lamports.saturating_sub(self.crank_rewards_paid))
+    }
+    
+    // Summary of the round for the distribution history
+    pub fn summary(&self, ended_at: i64) -> RoundSummary {
+        RoundSummary {
+            epoch: self.round_id,
+            started_at: self.started_at,
+            ended_at,
+            distributed_lamports: self.distributed_lamports,
+            crank_rewards_paid: self.crank_rewards_paid,
+            crank_reserve_returned: self.crank_reward_lamports.saturating_sub(self.crank_rewards_paid),
+            eligible_supply: self.eligible_supply,
+            holders_paid: self.holders_paid,
+            crank: self.started_by,
+        }
+    }
+}
+
+// Summary of a completed distribution round or a published Merkle distribution
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct RoundSummary {
+    /// Round number, which is also the distribution epoch it opened
+    pub epoch: u64,
+    
+    /// Round start timestamp
+    pub started_at: i64,
+    
+    /// Round completion timestamp
+    pub ended_at: i64,
+    
+    /// Lamports paid to holders
+    pub distributed_lamports: u64,
+    
+    /// Lamports paid to cranks
+    pub crank_rewards_paid: u64,
+    
+    /// Unpaid gas reserve returned to the pool balance
+    pub crank_reserve_returned: u64,
+    
+    /// Sum of the eligible balances paid
+    pub eligible_supply: u64,
+    
+    /// Number of token holders paid
+    pub holders_paid: u32,
+    
+    /// The crank that started the round
+    pub crank: Pubkey,
+}
+
+impl RoundSummary {
+    pub const LEN: usize = 8 + 8 + 8 + 8 + 8 + 8 + 8 + 4 + 32;
+}
+
+// Treasury withdrawal event, logged borsh-serialized with `sol_log_data`
+// (the base64 "Program data:" line) for indexers
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Default)]
+pub struct TreasuryWithdrawal {
+    /// The treasury authority that signed the withdrawal
+    pub authority: Pubkey,
+    
+    /// Token account the tokens were sent to
+    pub destination: Pubkey,
+    
+    /// Tokens withdrawn
+    pub amount: u64,
+    
+    /// Start of the withdrawal period the withdrawal counts against
+    pub period_start: i64,
+    
+    /// Tokens withdrawn in that period, this withdrawal included
+    pub withdrawn_in_period: u64,
+    
+    /// Tokens withdrawn from the treasury over its lifetime
+    pub total_withdrawn: u64,
+}
+
+// A transfer as the transfer rules see it
+#[derive(Debug, Clone, Copy)]
+pub struct TransferCheck {
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub source_balance: u64,
+    pub destination_balance: u64,
+    /// Tax split the transfer would be charged
+    pub split: TaxSplit,
+    pub exempt_from_limits: bool,
+    /// Last sell of the source's owner, if it ever sold
+    pub last_sell_timestamp: Option<i64>,
+}
+
+// Transfer rules a transfer breaks
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct TransferViolations {
+    /// The program is paused
+    pub paused: bool,
+    
+    /// The source holds less than the amount
+    pub insufficient_balance: bool,
+    
+    /// Buys are not open yet
+    pub trading_not_enabled: bool,
+    
+    /// Amount is above the maximum transaction amount
+    pub exceeds_max_transaction: bool,
+    
+    /// The source's owner sold too recently
+    pub sell_cooldown_active: bool,
+    
+    /// The destination would exceed the maximum holding
+    pub exceeds_max_holding: bool,
+    
+    /// Slippage protection is active
+    pub slippage_protection_active: bool,
+    
+    /// The tier routes a treasury share, but the treasury vault does not exist
+    pub treasury_not_initialized: bool,
+}
+
+impl TransferViolations {
+    // The error `TransferWithTax` fails with, in the order it checks the rules
+    pub fn first_error(&self) -> Option<SolDripError> {
+        [
+            (self.paused, SolDripError::ProgramPaused),
+            (self.insufficient_balance, SolDripError::InsufficientTokenBalance),
+            (self.trading_not_enabled, SolDripError::TradingNotEnabled),
+            (self.exceeds_max_transaction, SolDripError::ExceedsMaxTransactionAmount),
+            (self.sell_cooldown_active, SolDripError::SellCooldownActive),
+            (self.exceeds_max_holding, SolDripError::ExceedsMaximumHolding),
+            (self.slippage_protection_active, SolDripError::SlippageProtectionActive),
+            (self.treasury_not_initialized, SolDripError::TreasuryNotInitialized),
+        ]
+        .into_iter()
+        .find_map(|(violated, err)| violated.then_some(err))
+    }
+}
+
+// Result of `QuoteTransfer`, returned as borsh-serialized return data
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Default)]
+pub struct TransferQuote {
+    /// Amount quoted
+    pub amount: u64,
+    
+    /// Tokens the destination would receive
+    pub transfer_amount: u64,
+    
+    /// Tokens sent to the LP pool
+    pub lp_tax: u64,
+    
+    /// Tokens sent to the dividend pool and reward streams
+    pub dividend_tax: u64,
+    
+    /// Tokens burned
+    pub burn_tax: u64,
+    
+    /// Tokens sent to the treasury vault
+    pub treasury_tax: u64,
+    
+    /// Index of the size tier in the tax schedule
+    pub tier_index: u8,
+    
+    /// Total tax charged (basis points), including any launch tax
+    pub tax_bps: u16,
+    
+    /// The tax is split for buybacks because price fluctuation is high
+    pub buyback_mode: bool,
+    
+    /// Transfer rules that would reject the transfer
+    pub violations: TransferViolations,
+}
+
+impl TransferQuote {
+    // Whether `TransferWithTax` would fail on a transfer rule
+    pub fn rejected(&self) -> bool {
+        self.violations.first_error().is_some()
+    }
+}
+
+// Reward tokens claimable from one reward stream
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct ClaimableReward {
+    /// The reward token mint
+    pub reward_mint: Pubkey,
+    
+    /// Reward tokens `ClaimRewards` would pay now
+    pub amount: u64,
+}
+
+// Result of `GetClaimable`, returned as borsh-serialized return data
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct ClaimableDividends {
+    /// The token holder account
+    pub token_account: Pubkey,
+    
+    /// SOL `ClaimDividends` would pay now (lamports)
+    pub claimable_lamports: u64,
+    
+    /// Whether the claim is compounded into DRIP
+    pub compound: bool,
+    
+    /// Current dividend weight multiplier (basis points)
+    pub multiplier_bps: u64,
+    
+    /// Multiplier at the next bonus milestone, 0 if there is none
+    pub next_multiplier_bps: u64,
+    
+    /// Timestamp the next bonus milestone is reached at, 0 if there is none
+    pub next_milestone_timestamp: i64,
+    
+    /// Claimable rewards per reward stream, in account order
+    pub rewards: Vec<ClaimableReward>,
+}
+
+// The summaries a `DistributionHistory` holds. Named so the `ShankAccount`
+// derive, which only accepts literal array lengths, sees a plain type.
+pub type RoundSummaries = [RoundSummary; DISTRIBUTION_HISTORY_CAPACITY];
+
+// Ring buffer of the most recent completed rounds and published Merkle
+// distributions, one PDA per state account
+#[derive(BorshSerialize, BorshDeserialize, Debug, ShankAccount)]
+pub struct DistributionHistory {
+    /// The state account this history belongs to
+    pub state: Pubkey,
+    
+    /// Rounds recorded since the history was created, including overwritten ones
+    pub total_rounds: u64,
+    
+    /// Round summaries; round `n` (counting from 0) lives at `n % DISTRIBUTION_HISTORY_CAPACITY`
+    pub entries: RoundSummaries,
+    
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl DistributionHistory {
+    pub const LEN: usize = 32 + 8 + RoundSummary::LEN * DISTRIBUTION_HISTORY_CAPACITY + 1;
+    
+    // Append a round, overwriting the oldest once the buffer is full
+    pub fn record(&mut self, summary: RoundSummary) {
+        let slot = (self.total_rounds % DISTRIBUTION_HISTORY_CAPACITY as u64) as usize;
+        self.entries[slot] = summary;
+        self.total_rounds += 1;
+    }
+    
+    // Recorded rounds, newest first
+    pub fn rounds(&self) -> impl Iterator<Item = &RoundSummary> {
+        let len = self.total_rounds.min(DISTRIBUTION_HISTORY_CAPACITY as u64);
+        (1..=len).map(move |age| {
+            let slot = ((self.total_rounds - age) % DISTRIBUTION_HISTORY_CAPACITY as u64) as usize;
+            &self.entries[slot]
+        })
+    }
+}
+
+// A published Merkle distribution, one PDA per distribution id
+#[derive(BorshSerialize, BorshDeserialize, Debug, ShankAccount)]
+pub struct MerkleDistribution {
+    /// The state account this distribution belongs to
+    pub state: Pubkey,
+    
+    /// Sequential distribution number, starting at 1
+    pub distribution_id: u64,
+    
+    /// Distribution epoch opened by this distribution; amounts are computed
+    /// like a round starting this epoch
+    pub epoch: u64,
+    
+    /// Root of the tree of (index, holder, amount) leaves
+    pub merkle_root: [u8; 32],
+    
+    /// Lamports reserved in the dividend pool for all leaves together
+    pub total_amount: u64,
+    
+    /// Lamports claimed so far
+    pub claimed_amount: u64,
+    
+    /// Number of leaves in the tree
+    pub claim_count: u32,
+    
+    /// Number of leaves claimed so far
+    pub claims_made: u32,
+    
+    /// Publication timestamp
+    pub published_at: i64,
+    
+    /// The account that published the root
+    pub publisher: Pubkey,
+    
+    /// PDA bump seed
+    pub bump: u8,
+    
+    /// Claims are accepted until this timestamp
+    pub expires_at: i64,
+    
+    /// Unclaimed lamports returned to the pool balance after expiry
+    pub reclaimed_amount: u64,
+    
+    /// Whether the unclaimed lamports were returned to the pool balance
+    pub reclaimed: bool,
+}
+
+impl MerkleDistribution {
+    pub const LEN: usize = 32 + 8 + 8 + 32 + 8 + 8 + 4 + 4 + 8 + 32 + 1 + 8 + 8 + 1;
+    
+    // Number of claim bitmap accounts covering the distribution's leaves
+    pub fn bitmap_count(&self) -> u32 {
+        self.claim_count.div_ceil(MERKLE_CLAIMS_PER_BITMAP)
+    }
+    
+    // Whether the claim period has ended at `now`
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expires_at
+    }
+    
+    // Lamports still reserved for unclaimed leaves
+    pub fn unclaimed_amount(&self) -> u64 {
+        match self.reclaimed {
+            true => 0,
+            false => self.total_amount - self.claimed_amount,
+        }
+    }
+    
+    // History entry for the distribution. The lamports count as distributed
+    // when they are reserved for claims, and the eligible supply is only
+    // known off-chain.
+    pub fn summary(&self) -> RoundSummary {
+        RoundSummary {
+            epoch: self.epoch,
+            started_at: self.published_at,
+            ended_at: self.published_at,
+            distributed_lamports: self.total_amount,
+            crank_rewards_paid: 0,
+            crank_reserve_returned: 0,
+            eligible_supply: 0,
+            holders_paid: self.claim_count,
+            crank: self.publisher,
+        }
+    }
+}
+
+// Header of a claim bitmap account. The account data continues with
+// `MERKLE_CLAIM_BITMAP_BYTES` bytes of claim flags, one bit per leaf, which
+// are read and written in place rather than deserialized.
+#[derive(BorshSerialize, BorshDeserialize, Debug, ShankAccount)]
+pub struct MerkleClaimBitmap {
+    /// The Merkle distribution this bitmap belongs to
+    pub distribution: Pubkey,
+    
+    /// Position of this bitmap; it covers leaves from
+    /// `bitmap_index * MERKLE_CLAIMS_PER_BITMAP` on
+    pub bitmap_index: u32,
+    
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl MerkleClaimBitmap {
+    pub const HEADER_LEN: usize = 32 + 4 + 1;
+    pub const LEN: usize = Self::HEADER_LEN + MERKLE_CLAIM_BITMAP_BYTES;
+    
+    // Byte offset and mask of a leaf's flag in the account data
+    fn flag_position(index: u32) -> (usize, u8) {
+        let bit = index % MERKLE_CLAIMS_PER_BITMAP;
+        (Self::HEADER_LEN + (bit / 8) as usize, 1 << (bit % 8))
+    }
+    
+    // Whether the leaf at `index` has been claimed, given the bitmap's account data
+    pub fn is_claimed(data: &[u8], index: u32) -> bool {
+        let (offset, mask) = Self::flag_position(index);
+        data[offset] & mask != 0
+    }
+    
+    // Flag the leaf at `index` as claimed in the bitmap's account data
+    pub fn set_claimed(data: &mut [u8], index: u32) {
+        let (offset, mask) = Self::flag_position(index);
+        data[offset] |= mask;
+    }
+}
+
+// Hash of a Merkle distribution leaf: `holder` is owed `amount` lamports,
+// claimable once through the bitmap flag at `index`
+pub fn merkle_leaf(index: u32, holder: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[&[MERKLE_LEAF_PREFIX], &index.to_le_bytes(), holder.as_ref(), &amount.to_le_bytes()]).to_bytes()
+}
+
+// Hash of an inner Merkle node. The children are hashed in ascending order,
+// so proofs carry no left/right flags; a node without a sibling is carried
+// up to the next level unchanged.
+pub fn merkle_parent(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[&[MERKLE_NODE_PREFIX], left, right]).to_bytes()
+}
+
+// Check a leaf against a root, hashing in the sibling hashes from the leaf up
+pub fn verify_merkle_proof(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    proof.iter().fold(leaf, |node, sibling| merkle_parent(&node, sibling)) == *root
+}
+
+// Derive the distribution round PDA for a state account
+pub fn find_distribution_round_address(program_id: &Pubkey, state_account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DISTRIBUTION_ROUND_SEED, state_account.as_ref()], program_id)
+}
+
+// Derive the distribution history PDA for a state account
+pub fn find_distribution_history_address(program_id: &Pubkey, state_account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DISTRIBUTION_HISTORY_SEED, state_account.as_ref()], program_id)
+}
+
+// Derive the dividend pool PDA for a state account
+pub fn find_dividend_pool_address(program_id: &Pubkey, state_account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DIVIDEND_POOL_SEED, state_account.as_ref()], program_id)
+}
+
+// Derive the reward stream PDA for a reward mint
+pub fn find_reward_stream_address(program_id: &Pubkey, state_account: &Pubkey, reward_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REWARD_STREAM_SEED, state_account.as_ref(), reward_mint.as_ref()], program_id)
+}
+
+// Derive a reward stream's vault token account
+pub fn find_reward_vault_address(program_id: &Pubkey, reward_stream: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REWARD_VAULT_SEED, reward_stream.as_ref()], program_id)
+}
+
+// Derive a reward stream's DRIP tax token account
+pub fn find_reward_tax_address(program_id: &Pubkey, reward_stream: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REWARD_TAX_SEED, reward_stream.as_ref()], program_id)
+}
+
+// Derive the reward position PDA for a token account in a stream
+pub fn find_reward_position_address(program_id: &Pubkey, reward_stream: &Pubkey, token_account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[REWARD_POSITION_SEED, reward_stream.as_ref(), token_account.as_ref()],
+        program_id,
+    )
+}
+
+// Derive the treasury vault PDA for a state account
+pub fn find_treasury_vault_address(program_id: &Pubkey, state_account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TREASURY_VAULT_SEED, state_account.as_ref()], program_id)
+}
+
+// Derive the Merkle distribution PDA for a distribution id
+pub fn find_merkle_distribution_address(program_id: &Pubkey, state_account: &Pubkey, distribution_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[MERKLE_DISTRIBUTION_SEED, state_account.as_ref(), &distribution_id.to_le_bytes()],
+        program_id,
+    )
+}
+
+// Derive a Merkle distribution's claim bitmap PDA
+pub fn find_merkle_claim_bitmap_address(program_id: &Pubkey, distribution: &Pubkey, bitmap_index: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[MERKLE_CLAIM_BITMAP_SEED, distribution.as_ref(), &bitmap_index.to_le_bytes()],
+        program_id,
+    )
+}
+
+// Derive the holder record PDA for a token account
+pub fn find_holder_record_address(program_id: &Pubkey, state_account: &Pubkey, token_account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[HOLDER_RECORD_SEED, state_account.as_ref(), token_account.as_ref()],
+        program_id,
+    )
+}
+
+// Derive the seller record PDA of a wallet
+pub fn find_seller_record_address(program_id: &Pubkey, state_account: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SELLER_RECORD_SEED, state_account.as_ref(), owner.as_ref()],
+        program_id,
+    )
+}
+
+// Build an Initialize instruction. The mint, LP pool and state accounts
+// are created by the instruction and must sign. The dividend pool's
+// associated token account is left to the caller.
+pub fn initialize(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    lp_pool: &Pubkey,
+    state_account: &Pubkey,
+    total_supply: u64,
+) -> Result<Instruction, ProgramError> {
+    let (dividend_pool, _) = find_dividend_pool_address(program_id, state_account);
+    
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(*mint, true),
+            AccountMeta::new(dividend_pool, false),
+            AccountMeta::new(*lp_pool, true),
+            AccountMeta::new(*state_account, true),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: SolDripInstruction::Initialize { total_supply }.try_to_vec()?,
+    })
+}
+
+// Build a TransferWithTax instruction. `reward_streams` must list every
+// reward stream of the state account. The sender is taken to own `source`.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_with_tax(
+    program_id: &Pubkey,
+    sender: &Pubkey,
+    state_account: &Pubkey,
+    state: &SolDripState,
+    source: &Pubkey,
+    destination: &Pubkey,
+    reward_streams: &[Pubkey],
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let (source_record, _) = find_holder_record_address(program_id, state_account, source);
+    let (destination_record, _) = find_holder_record_address(program_id, state_account, destination);
+    let (treasury_vault, _) = find_treasury_vault_address(program_id, state_account);
+    let (seller_record, _) = find_seller_record_address(program_id, state_account, sender);
+    let mut accounts = vec![
+        AccountMeta::new(*sender, true),
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new(state.dividend_tax_account(), false),
+        AccountMeta::new(state.lp_pool, false),
+        AccountMeta::new(*state_account, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new(source_record, false),
+        AccountMeta::new(state.mint, false),
+        AccountMeta::new(treasury_vault, false),
+        AccountMeta::new(destination_record, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(seller_record, false),
+    ];
+    
+    let mut reward_streams = reward_streams.to_vec();
+    reward_streams.sort();
+    for reward_stream in reward_streams {
+        let (tax_account, _) = find_reward_tax_address(program_id, &reward_stream);
+        let (source_position, _) = find_reward_position_address(program_id, &reward_stream, source);
+        let (destination_position, _) = find_reward_position_address(program_id, &reward_stream, destination);
+        accounts.push(AccountMeta::new(reward_stream, false));
+        accounts.push(AccountMeta::new(tax_account, false));
+        accounts.push(AccountMeta::new(source_position, false));
+        accounts.push(AccountMeta::new(destination_position, false));
+    }
+    
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data: SolDripInstruction::TransferWithTax { amount }.try_to_vec()?,
+    })
+}
+
+// Build a DistributeDividends instruction for the given
+// (token holder account, dividend recipient) pairs
+pub fn distribute_dividends(
+    program_id: &Pubkey,
+    distributor: &Pubkey,
+    dividend_pool: &Pubkey,
+    state_account: &Pubkey,
+    token_holders: &[(Pubkey, Pubkey)],
+) -> Result<Instruction, ProgramError> {
+    let (distribution_round, _) = find_distribution_round_address(program_id, state_account);
+    let mut accounts = vec![
+        AccountMeta::new(*distributor, true),
+        AccountMeta::new(*dividend_pool, false),
+        AccountMeta::new(*state_account, false),
+        AccountMeta::new(distribution_round, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    for (token_account, recipient) in token_holders {
+        let (holder_record, _) = find_holder_record_address(program_id, state_account, token_account);
+        accounts.push(AccountMeta::new_readonly(*token_account, false));
+        accounts.push(AccountMeta::new(holder_record, false));
+        accounts.push(AccountMeta::new(*recipient, false));
+    }
+    
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data: SolDripInstruction::DistributeDividends.try_to_vec()?,
+    })
+}
+
+// Build a CompleteDistributionRound instruction
+pub fn complete_distribution_round(
+    program_id: &Pubkey,
+    caller: &Pubkey,
+    state_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let (distribution_round, _) = find_distribution_round_address(program_id, state_account);
+    let (distribution_history, _) = find_distribution_history_address(program_id, state_account);
+    
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*caller, true),
+            AccountMeta::new(distribution_round, false),
+            AccountMeta::new(*state_account, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new(distribution_history, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SolDripInstruction::CompleteDistributionRound.try_to_vec()?,
+    })
+}
+
+// Build an EnableTrading instruction
+pub fn enable_trading(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    state_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*state_account, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data: SolDripInstruction::EnableTrading.try_to_vec()?,
+    })
+}
+
+// Build a SetLimitExemption instruction
+pub fn set_limit_exemption(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    state_account: &Pubkey,
+    token_holder: &Pubkey,
+    exempt: bool,
+) -> Result<Instruction, ProgramError> {
+    let (holder_record, _) = find_holder_record_address(program_id, state_account, token_holder);
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*state_account, false),
+            AccountMeta::new_readonly(*token_holder, false),
+            AccountMeta::new(holder_record, false),
+        ],
+        data: SolDripInstruction::SetLimitExemption { exempt }.try_to_vec()?,
+    })
+}
+
+// Build a SetPaused instruction
+pub fn set_paused(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    state_account: &Pubkey,
+    paused: bool,
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*state_account, false),
+        ],
+        data: SolDripInstruction::SetPaused { paused }.try_to_vec()?,
+    })
+}
+
+// Build a DepositDividends instruction
+pub fn deposit_dividends(
+    program_id: &Pubkey,
+    depositor: &Pubkey,
+    state_account: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let (dividend_pool, _) = find_dividend_pool_address(program_id, state_account);
+    
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*depositor, true),
+            AccountMeta::new(*state_account, false),
+            AccountMeta::new(dividend_pool, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SolDripInstruction::DepositDividends { amount }.try_to_vec()?,
+    })
+}
+
+// Build a SyncPool instruction
+pub fn sync_pool(program_id: &Pubkey, state_account: &Pubkey) -> Result<Instruction, ProgramError> {
+    let (dividend_pool, _) = find_dividend_pool_address(program_id, state_account);
+    
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*state_account, false),
+            AccountMeta::new_readonly(dividend_pool, false),
+        ],
+        data: SolDripInstruction::SyncPool.try_to_vec()?,
+    })
+}
+
+// Build an InitializeHolder instruction
+pub fn initialize_holder(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    state_account: &Pubkey,
+    token_account: &Pubkey,
+    reward_streams: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let (holder_record, _) = find_holder_record_address(program_id, state_account, token_account);
+    let mut accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*state_account, false),
+        AccountMeta::new_readonly(*token_account, false),
+        AccountMeta::new(holder_record, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    let mut reward_streams = reward_streams.to_vec();
+    reward_streams.sort();
+    accounts.extend(reward_streams.iter().map(|reward_stream| AccountMeta::new_readonly(*reward_stream, false)));
+    
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data: SolDripInstruction::InitializeHolder.try_to_vec()?,
+    })
+}
+
+// Build a SetPayoutAddress instruction
+pub fn set_payout_address(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    state_account: &Pubkey,
+    token_account: &Pubkey,
+    payout_address: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let (holder_record, _) = find_holder_record_address(program_id, state_account, token_account);
+    
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(*token_account, false),
+            AccountMeta::new(holder_record, false),
+        ],
+        data: SolDripInstruction::SetPayoutAddress { payout_address: *payout_address }.try_to_vec()?,
+    })
+}
+
+// Build a SetCompounding instruction
+pub fn set_compounding(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    state_account: &Pubkey,
+    token_account: &Pubkey,
+    enabled: bool,
+) -> Result<Instruction, ProgramError> {
+    let (holder_record, _) = find_holder_record_address(program_id, state_account, token_account);
+    
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(*token_account, false),
+            AccountMeta::new(holder_record, false),
+        ],
+        data: SolDripInstruction::SetCompounding { enabled }.try_to_vec()?,
+    })
+}
+
+// Build a ClaimDividends instruction. `swap_accounts` is the swap adapter
+// program followed by the accounts it needs, and is only used when compounding.
+pub fn claim_dividends(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    state_account: &Pubkey,
+    token_account: &Pubkey,
+    recipient: &Pubkey,
+    swap_accounts: &[AccountMeta],
+    minimum_amount_out: u64,
+) -> Result<Instruction, ProgramError> {
+    let (dividend_pool, _) = find_dividend_pool_address(program_id, state_account);
+    let (holder_record, _) = find_holder_record_address(program_id, state_account, token_account);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new(*state_account, false),
+        AccountMeta::new(dividend_pool, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new(holder_record, false),
+        AccountMeta::new(*recipient, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend_from_slice(swap_accounts);
+    
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data: SolDripInstruction::ClaimDividends { minimum_amount_out }.try_to_vec()?,
+    })
+}
+
+// Build a ConvertStreamTax instruction for the reward stream of `reward_mint`.
+// `swap_accounts` are forwarded to the swap adapter.
+pub fn convert_stream_tax(
+    program_id: &Pubkey,
+    cranker: &Pubkey,
+    state_account: &Pubkey,
+    reward_mint: &Pubkey,
+    swap_program: &Pubkey,
+    swap_accounts: &[AccountMeta],
+    minimum_amount_out: u64,
+) -> Result<Instruction, ProgramError> {
+    let (reward_stream, _) = find_reward_stream_address(program_id, state_account, reward_mint);
+    let (tax_account, _) = find_reward_tax_address(program_id, &reward_stream);
+    let (vault, _) = find_reward_vault_address(program_id, &reward_stream);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*cranker, true),
+        AccountMeta::new_readonly(*state_account, false),
+        AccountMeta::new(reward_stream, false),
+        AccountMeta::new(tax_account, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(*swap_program, false),
+    ];
+    accounts.extend_from_slice(swap_accounts);
+    
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data: SolDripInstruction::ConvertStreamTax { minimum_amount_out }.try_to_vec()?,
+    })
+}
+
+// Build a ClaimRewards instruction for the given
+// (reward mint, owner's reward token account) pairs
+pub fn claim_rewards(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    state_account: &Pubkey,
+    token_account: &Pubkey,
+    rewards: &[(Pubkey, Pubkey)],
+) -> Result<Instruction, ProgramError> {
+    let mut accounts = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new_readonly(*state_account, false),
+        AccountMeta::new_readonly(*token_account, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    for (reward_mint, destination) in rewards {
+        let (reward_stream, _) = find_reward_stream_address(program_id, state_account, reward_mint);
+        let (vault, _) = find_reward_vault_address(program_id, &reward_stream);
+        let (position, _) = find_reward_position_address(program_id, &reward_stream, token_account);
+        accounts.push(AccountMeta::new(reward_stream, false));
+        accounts.push(AccountMeta::new(vault, false));
+        accounts.push(AccountMeta::new(position, false));
+        accounts.push(AccountMeta::new(*destination, false));
+    }
+    
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data: SolDripInstruction::ClaimRewards.try_to_vec()?,
+    })
+}
+
+// Build a WithdrawTreasury instruction
+pub fn withdraw_treasury(
+    program_id: &Pubkey,
+    treasury_authority: &Pubkey,
+    state_account: &Pubkey,
+    destination: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let (treasury_vault, _) = find_treasury_vault_address(program_id, state_account);
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*treasury_authority, true),
+            AccountMeta::new(*state_account, false),
+            AccountMeta::new(treasury_vault, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data: SolDripInstruction::WithdrawTreasury { amount }.try_to_vec()?,
+    })
+}
+
+// Build a QuoteTransfer instruction for a transfer from `sender`, a token
+// account owned by `owner`
+pub fn quote_transfer(
+    program_id: &Pubkey,
+    state_account: &Pubkey,
+    owner: &Pubkey,
+    sender: &Pubkey,
+    destination: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let (holder_record, _) = find_holder_record_address(program_id, state_account, sender);
+    let (seller_record, _) = find_seller_record_address(program_id, state_account, owner);
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*state_account, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(*sender, false),
+            AccountMeta::new_readonly(*destination, false),
+            AccountMeta::new_readonly(holder_record, false),
+            AccountMeta::new_readonly(seller_record, false),
+        ],
+        data: SolDripInstruction::QuoteTransfer { amount, sender: *sender, destination: *destination }.try_to_vec()?,
+    })
+}
+
+// Build a GetClaimable instruction for a token account and the given reward mints
+pub fn get_claimable(
+    program_id: &Pubkey,
+    state_account: &Pubkey,
+    token_account: &Pubkey,
+    reward_mints: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let (holder_record, _) = find_holder_record_address(program_id, state_account, token_account);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*state_account, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(*token_account, false),
+        AccountMeta::new_readonly(holder_record, false),
+    ];
+    for reward_mint in reward_mints {
+        let (reward_stream, _) = find_reward_stream_address(program_id, state_account, reward_mint);
+        let (vault, _) = find_reward_vault_address(program_id, &reward_stream);
+        let (position, _) = find_reward_position_address(program_id, &reward_stream, token_account);
+        accounts.push(AccountMeta::new_readonly(reward_stream, false));
+        accounts.push(AccountMeta::new_readonly(vault, false));
+        accounts.push(AccountMeta::new_readonly(position, false));
+    }
+    
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data: SolDripInstruction::GetClaimable.try_to_vec()?,
+    })
+}
+
+// Build a PublishMerkleRoot instruction. `distribution_id` must be the
+// state's `merkle_distribution_count + 1`.
+pub fn publish_merkle_root(
+    program_id: &Pubkey,
+    publisher: &Pubkey,
+    state_account: &Pubkey,
+    distribution_id: u64,
+    merkle_root: [u8; 32],
+    total_amount: u64,
+    claim_count: u32,
+) -> Result<Instruction, ProgramError> {
+    let (distribution, _) = find_merkle_distribution_address(program_id, state_account, distribution_id);
+    let (round, _) = find_distribution_round_address(program_id, state_account);
+    let (history, _) = find_distribution_history_address(program_id, state_account);
+    let mut accounts = vec![
+        AccountMeta::new(*publisher, true),
+        AccountMeta::new(*state_account, false),
+        AccountMeta::new(distribution, false),
+        AccountMeta::new_readonly(round, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(history, false),
+    ];
+    for bitmap_index in 0..claim_count.div_ceil(MERKLE_CLAIMS_PER_BITMAP) {
+        let (bitmap, _) = find_merkle_claim_bitmap_address(program_id, &distribution, bitmap_index);
+        accounts.push(AccountMeta::new(bitmap, false));
+    }
+    
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data: SolDripInstruction::PublishMerkleRoot { merkle_root, total_amount, claim_count }.try_to_vec()?,
+    })
+}
+
+// Build a ClaimMerkleDividend instruction
+pub fn claim_merkle_dividend(
+    program_id: &Pubkey,
+    holder: &Pubkey,
+    state_account: &Pubkey,
+    distribution_id: u64,
+    index: u32,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> Result<Instruction, ProgramError> {
+    let (distribution, _) = find_merkle_distribution_address(program_id, state_account, distribution_id);
+    let (bitmap, _) = find_merkle_claim_bitmap_address(program_id, &distribution, index / MERKLE_CLAIMS_PER_BITMAP);
+    let (dividend_pool, _) = find_dividend_pool_address(program_id, state_account);
+    
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*holder, true),
+            AccountMeta::new(*state_account, false),
+            AccountMeta::new(distribution, false),
+            AccountMeta::new(bitmap, false),
+            AccountMeta::new(dividend_pool, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SolDripInstruction::ClaimMerkleDividend { index, amount, proof }.try_to_vec()?,
+    })
+}
+
+// Build a ReclaimMerkleDistribution instruction
+pub fn reclaim_merkle_distribution(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    state_account: &Pubkey,
+    distribution_id: u64,
+) -> Result<Instruction, ProgramError> {
+    let (distribution, _) = find_merkle_distribution_address(program_id, state_account, distribution_id);
+    
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*state_account, false),
+            AccountMeta::new(distribution, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data: SolDripInstruction::ReclaimMerkleDistribution.try_to_vec()?,
+    })
+}
+
+// Build a SweepRewardStream instruction
+pub fn sweep_reward_stream(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    state_account: &Pubkey,
+    reward_mint: &Pubkey,
+    destination: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let (reward_stream, _) = find_reward_stream_address(program_id, state_account, reward_mint);
+    let (vault, _) = find_reward_vault_address(program_id, &reward_stream);
+    
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*state_account, false),
+            AccountMeta::new(reward_stream, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: SolDripInstruction::SweepRewardStream.try_to_vec()?,
+    })
+}
+
+// Build an UpdateConfig instruction
+pub fn update_config(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    state_account: &Pubkey,
+    config: SolDripConfig,
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*state_account, false),
+        ],
+        data: SolDripInstruction::UpdateConfig { config: Box::new(config) }.try_to_vec()?,
+    })
+}
+
+// Process instruction entrypoint
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    // Deserialize instruction
+    let instruction = SolDripInstruction::try_from_slice(instruction_data)
+        .map_err(|_| SolDripError::InvalidInstruction)?;
+    
+    // Process the instruction
+    match instruction {
+        SolDripInstruction::Initialize { total_supply } => {
+            msg!("Instruction: Initialize");
+            process_initialize(program_id, accounts, total_supply)
+        },
+        SolDripInstruction::TransferWithTax { amount } => {
+            msg!("Instruction: TransferWithTax");
+            process_transfer_with_tax(program_id, accounts, amount)
+        },
+        SolDripInstruction::DistributeDividends => {
+            msg!("Instruction: DistributeDividends");
+            process_distribute_dividends(program_id, accounts)
+        },
+        SolDripInstruction::CompleteDistributionRound => {
+            msg!("Instruction: CompleteDistributionRound");
+            process_complete_distribution_round(program_id, accounts)
+        },
+        SolDripInstruction::InitializeHolder => {
+            msg!("Instruction: InitializeHolder");
+            process_initialize_holder(program_id, accounts)
+        },
+        SolDripInstruction::SetPayoutAddress { payout_address } => {
+            msg!("Instruction: SetPayoutAddress");
+            process_set_payout_address(program_id, accounts, payout_address)
+        },
+        SolDripInstruction::SetCompounding { enabled } => {
+            msg!("Instruction: SetCompounding");
+            process_set_compounding(program_id, accounts, enabled)
+        },
+        SolDripInstruction::ClaimDividends { minimum_amount_out } => {
+            msg!("Instruction: ClaimDividends");
+            process_claim_dividends(program_id, accounts, minimum_amount_out)
+        },
+        SolDripInstruction::UpdateConfig { config } => {
+            msg!("Instruction: UpdateConfig");
+            process_update_config(program_id, accounts, *config)
+        },
+        SolDripInstruction::EnableTrading => {
+            msg!("Instruction: EnableTrading");
+            process_enable_trading(program_id, accounts)
+        },
+        SolDripInstruction::SetLimitExemption { exempt } => {
+            msg!("Instruction: SetLimitExemption");
+            process_set_limit_exemption(program_id, accounts, exempt)
+        },
+        SolDripInstruction::DepositDividends { amount } => {
+            msg!("Instruction: DepositDividends");
+            process_deposit_dividends(program_id, accounts, amount)
+        },
+        SolDripInstruction::SyncPool => {
+            msg!("Instruction: SyncPool");
+            process_sync_pool(program_id, accounts)
+        },
+        SolDripInstruction::CreateRewardStream { tax_share_bps } => {
+            msg!("Instruction: CreateRewardStream");
+            process_create_reward_stream(program_id, accounts, tax_share_bps)
+        },
+        SolDripInstruction::FundRewardStream { amount } => {
+            msg!("Instruction: FundRewardStream");
+            process_fund_reward_stream(program_id, accounts, amount)
+        },
+        SolDripInstruction::ConvertStreamTax { minimum_amount_out } => {
+            msg!("Instruction: ConvertStreamTax");
+            process_convert_stream_tax(program_id, accounts, minimum_amount_out)
+        },
+        SolDripInstruction::ClaimRewards => {
+            msg!("Instruction: ClaimRewards");
+            process_claim_rewards(program_id, accounts)
+        },
+        SolDripInstruction::InitializeTreasury => {
+            msg!("Instruction: InitializeTreasury");
+            process_initialize_treasury(program_id, accounts)
+        },
+        SolDripInstruction::WithdrawTreasury { amount } => {
+            msg!("Instruction: WithdrawTreasury");
+            process_withdraw_treasury(program_id, accounts, amount)
+        },
+        SolDripInstruction::QuoteTransfer { amount, sender, destination } => {
+            msg!("Instruction: QuoteTransfer");
+            process_quote_transfer(program_id, accounts, amount, &sender, &destination)
+        },
+        SolDripInstruction::GetClaimable => {
+            msg!("Instruction: GetClaimable");
+            process_get_claimable(program_id, accounts)
+        },
+        SolDripInstruction::SetPaused { paused } => {
+            msg!("Instruction: SetPaused");
+            process_set_paused(program_id, accounts, paused)
+        },
+        SolDripInstruction::PublishMerkleRoot { merkle_root, total_amount, claim_count } => {
+            msg!("Instruction: PublishMerkleRoot");
+            process_publish_merkle_root(program_id, accounts, merkle_root, total_amount, claim_count)
+        },
+        SolDripInstruction::ClaimMerkleDividend { index, amount, proof } => {
+            msg!("Instruction: ClaimMerkleDividend");
+            process_claim_merkle_dividend(program_id, accounts, index, amount, &proof)
+        },
+        SolDripInstruction::ReclaimMerkleDistribution => {
+            msg!("Instruction: ReclaimMerkleDistribution");
+            process_reclaim_merkle_distribution(program_id, accounts)
+        },
+        SolDripInstruction::SweepRewardStream => {
+            msg!("Instruction: SweepRewardStream");
+            process_sweep_reward_stream(program_id, accounts)
+        },
+    }
+}
+
+// Initialize a new SolDrip token
+fn process_initialize(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    total_supply: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let initializer = next_account_info(account_info_iter)?;
+    let mint_account = next_account_info(account_info_iter)?;
+    let dividend_pool = next_account_info(account_info_iter)?;
+    let lp_pool = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let rent_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !initializer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify dividend pool PDA
+    let (dividend_pool_address, dividend_pool_bump) = find_dividend_pool_address(program_id, state_account.key);
+    if *dividend_pool.key != dividend_pool_address {
+        return Err(SolDripError::InvalidDividendPool.into());
+    }
+    
+    // Create mint account
+    let rent = &Rent::from_account_info(rent_account)?;
+    let mint_space = Mint::LEN;
+    let mint_rent = rent.minimum_balance(mint_space);
     
     // Create mint with initializer as mint authority
     invoke(
         &system_instruction::create_account(
-            initializer.key,
-            mint_account.key,
-            mint_rent,
-            mint_space as u64,
+            initializer.key,
+            mint_account.key,
+            mint_rent,
+            mint_space as u64,
+            token_program.key,
+        ),
+        &[initializer.clone(), mint_account.clone()],
+    )?;
+    
+    // Initialize mint
+    invoke(
+        &token_instruction::initialize_mint(
+            token_program.key,
+            mint_account.key,
+            initializer.key,
+            Some(initializer.key),
+            9, // Decimals: 9 to match SOL
+        )?,
+        &[
+            mint_account.clone(),
+            rent_account.clone(),
+        ],
+    )?;
+    
+    // Initialize state account
+    let state_space = SolDripState::LEN;
+    let state_rent = rent.minimum_balance(state_space);
+    
+    invoke(
+        &system_instruction::create_account(
+            initializer.key,
+            state_account.key,
+            state_rent,
+            state_space as u64,
+            program_id,
+        ),
+        &[initializer.clone(), state_account.clone()],
+    )?;
+    
+    // Initialize dividend pool as a system-owned PDA so payouts are
+    // plain system transfers signed with the pool seeds
+    invoke(
+        &system_instruction::transfer(
+            initializer.key,
+            dividend_pool.key,
+            rent.minimum_balance(0),
+        ),
+        &[initializer.clone(), dividend_pool.clone()],
+    )?;
+    
+    // Initialize the LP pool as a token account held by the dividend pool,
+    // so the program can sign for the tokens it collects
+    invoke(
+        &system_instruction::create_account(
+            initializer.key,
+            lp_pool.key,
+            rent.minimum_balance(TokenAccount::LEN),
+            TokenAccount::LEN as u64,
+            token_program.key,
+        ),
+        &[initializer.clone(), lp_pool.clone()],
+    )?;
+    invoke(
+        &token_instruction::initialize_account3(
+            token_program.key,
+            lp_pool.key,
+            mint_account.key,
+            dividend_pool.key,
+        )?,
+        &[lp_pool.clone(), mint_account.clone(), token_program.clone()],
+    )?;
+    
+    // Initialize state data
+    let state = SolDripState {
+        mint: *mint_account.key,
+        dividend_pool: *dividend_pool.key,
+        lp_pool: *lp_pool.key,
+        total_sol_distributed: 0,
+        last_distribution_timestamp: 0,
+        price_fluctuation_bps: 0,
+        slippage_protection_active: false,
+        slippage_protection_timestamp: 0,
+        total_supply,
+        dividend_pool_bump,
+        authority: *initializer.key,
+        reserved_lamports: 0,
+        pool_balance: 0,
+        round_lamports: 0,
+        total_donations: 0,
+        config: SolDripConfig::default(),
+        reward_stream_count: 0,
+        reward_stream_tax_share_bps: 0,
+        trading_enabled: false,
+        launch_slot: 0,
+        total_burned: 0,
+        treasury_vault: Pubkey::default(),
+        treasury_period_start: 0,
+        treasury_withdrawn_in_period: 0,
+        total_treasury_withdrawn: 0,
+        distribution_epoch: 0,
+        paused: false,
+        merkle_distribution_count: 0,
+        total_weight: 0,
+    };
+    
+    state.serialize(&mut *state_account.data.borrow_mut())?;
+    
+    msg!("SolDrip token initialized with total supply: {}", total_supply);
+    
+    Ok(())
+}
+
+// Transfer tokens with tax
+fn process_transfer_with_tax(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let sender = next_account_info(account_info_iter)?;
+    let source = next_account_info(account_info_iter)?;
+    let destination = next_account_info(account_info_iter)?;
+    let dividend_tax_account = next_account_info(account_info_iter)?;
+    let lp_pool = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let clock_account = next_account_info(account_info_iter)?;
+    let holder_record_account = next_account_info(account_info_iter)?;
+    let mint_account = next_account_info(account_info_iter)?;
+    let treasury_vault = next_account_info(account_info_iter)?;
+    let destination_record_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    let seller_record_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !sender.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if *mint_account.key != state.mint {
+        return Err(SolDripError::InvalidTokenAccount.into());
+    }
+    if state.treasury_vault != Pubkey::default() && *treasury_vault.key != state.treasury_vault {
+        return Err(SolDripError::InvalidTreasuryVault.into());
+    }
+    if *dividend_tax_account.key != state.dividend_tax_account() {
+        return Err(SolDripError::InvalidDividendPool.into());
+    }
+    if *lp_pool.key != state.lp_pool {
+        return Err(SolDripError::InvalidLpPool.into());
+    }
+    
+    // Get clock
+    let clock = Clock::from_account_info(clock_account)?;
+    
+    // Verify token accounts
+    if !is_token_account(source, token_program.key) || !is_token_account(destination, token_program.key) {
+        return Err(SolDripError::InvalidTokenAccount.into());
+    }
+    
+    // Load token accounts data
+    let source_data = TokenAccount::unpack(&source.data.borrow())?;
+    let destination_data = TokenAccount::unpack(&destination.data.borrow())?;
+    
+    // Pick the tax tier for the transfer size, with the anti-sniper launch
+    // tax, re-split for buybacks while price fluctuation is high
+    let (_, tier) = state.transfer_tax_tier(amount, clock.slot);
+    let buyback_mode = state.buyback_mode();
+    let split = match buyback_mode {
+        true => tier.split(amount).into_buyback(),
+        false => tier.split(amount),
+    };
+    
+    // Enforce the transfer rules. The sell cooldown belongs to the source's
+    // owner, across all of its token accounts.
+    let mut holder_record = load_holder_record(program_id, state_account.key, source.key, holder_record_account)?;
+    let exempt = holder_record.as_ref().is_some_and(|record| record.exempt_from_limits);
+    let is_sell = state.is_sell(destination.key);
+    let seller_record = match is_sell {
+        true => load_seller_record(program_id, state_account.key, &source_data.owner, seller_record_account)?,
+        false => None,
+    };
+    let violations = state.transfer_violations(
+        &TransferCheck {
+            source: *source.key,
+            destination: *destination.key,
+            amount,
+            source_balance: source_data.amount,
+            destination_balance: destination_data.amount,
+            split,
+            exempt_from_limits: exempt,
+            last_sell_timestamp: seller_record.as_ref().map(|record| record.last_sell_timestamp),
+        },
+        clock.unix_timestamp,
+    );
+    if let Some(err) = violations.first_error() {
+        return Err(err.into());
+    }
+    
+    // Start a new sell cooldown
+    if is_sell && !exempt && state.config.sell_cooldown > 0 {
+        let mut seller_record = match seller_record {
+            Some(seller_record) => seller_record,
+            None => create_seller_record(
+                program_id,
+                sender,
+                state_account,
+                &source_data.owner,
+                seller_record_account,
+                system_program_account,
+            )?,
+        };
+        seller_record.record_sell(state.config.sell_cooldown, clock.unix_timestamp)?;
+        seller_record.serialize(&mut *seller_record_account.data.borrow_mut())?;
+    }
+    
+    // Slippage protection did not block the transfer, so it has expired
+    state.slippage_protection_active = false;
+    
+    // Every reward stream, in ascending order so each is passed once, with
+    // its tax account and the reward positions of both sides
+    let stream_accounts: Vec<&AccountInfo> = account_info_iter.collect();
+    let stream_groups = stream_accounts.chunks_exact(4);
+    if !stream_groups.remainder().is_empty() || stream_groups.len() != state.reward_stream_count as usize {
+        return Err(SolDripError::MissingRewardStreams.into());
+    }
+    let mut streams = Vec::with_capacity(stream_groups.len());
+    let mut previous_stream = Pubkey::default();
+    for stream_group in stream_groups {
+        if *stream_group[0].key <= previous_stream {
+            return Err(SolDripError::MissingRewardStreams.into());
+        }
+        previous_stream = *stream_group[0].key;
+        
+        let stream = load_reward_stream(program_id, state_account.key, stream_group[0])?;
+        if *stream_group[1].key != stream.tax_account {
+            return Err(SolDripError::InvalidRewardStream.into());
+        }
+        streams.push((stream, stream_group));
+    }
+    
+    // Check if price fluctuation is high
+    // In a real implementation, we would fetch current price from an oracle
+    // Here we're using a placeholder implementation
+    if buyback_mode {
+        // Activate slippage protection
+        state.slippage_protection_active = true;
+        state.slippage_protection_timestamp = clock.unix_timestamp;
+        
+        // TODO: Implement buyback logic using Jupiter aggregator
+        // For now, we'll just send the buyback share to the LP pool
+        for (pool, tax) in [(lp_pool, split.lp), (dividend_tax_account, split.dividend)] {
+            if tax == 0 {
+                continue;
+            }
+            invoke(
+                &token_instruction::transfer(
+                    token_program.key,
+                    source.key,
+                    pool.key,
+                    sender.key,
+                    &[],
+                    tax,
+                )?,
+                &[
+                    source.clone(),
+                    pool.clone(),
+                    sender.clone(),
+                    token_program.clone(),
+                ],
+            )?;
+        }
+        
+        msg!("Slippage protection activated: {} tokens used for buyback", split.lp);
+    } else {
+        // Normal tax distribution
+        
+        // Transfer LP tax
+        invoke(
+            &token_instruction::transfer(
+                token_program.key,
+                source.key,
+                lp_pool.key,
+                sender.key,
+                &[],
+                split.lp,
+            )?,
+            &[
+                source.clone(),
+                lp_pool.clone(),
+                sender.clone(),
+                token_program.clone(),
+            ],
+        )?;
+        
+        // Burn the tier's burn share
+        if split.burn > 0 {
+            invoke(
+                &token_instruction::burn(
+                    token_program.key,
+                    source.key,
+                    mint_account.key,
+                    sender.key,
+                    &[],
+                    split.burn,
+                )?,
+                &[
+                    source.clone(),
+                    mint_account.clone(),
+                    sender.clone(),
+                    token_program.clone(),
+                ],
+            )?;
+            state.total_burned += split.burn;
+        }
+        
+        // Transfer the treasury share to the treasury vault
+        if split.treasury > 0 {
+            invoke(
+                &token_instruction::transfer(
+                    token_program.key,
+                    source.key,
+                    treasury_vault.key,
+                    sender.key,
+                    &[],
+                    split.treasury,
+                )?,
+                &[
+                    source.clone(),
+                    treasury_vault.clone(),
+                    sender.clone(),
+                    token_program.clone(),
+                ],
+            )?;
+        }
+        
+        // Route each reward stream's share of the dividend tax to its tax account
+        let mut sol_dividend_tax = split.dividend;
+        for (stream, stream_group) in &streams {
+            let tax_account = stream_group[1];
+            let stream_tax = bps_of(split.dividend, stream.tax_share_bps);
+            if stream_tax == 0 {
+                continue;
+            }
+            
+            invoke(
+                &token_instruction::transfer(
+                    token_program.key,
+                    source.key,
+                    tax_account.key,
+                    sender.key,
+                    &[],
+                    stream_tax,
+                )?,
+                &[
+                    source.clone(),
+                    tax_account.clone(),
+                    sender.clone(),
+                    token_program.clone(),
+                ],
+            )?;
+            sol_dividend_tax -= stream_tax;
+        }
+        
+        // Transfer the remaining dividend tax to the dividend pool's token account
+        invoke(
+            &token_instruction::transfer(
+                token_program.key,
+                source.key,
+                dividend_tax_account.key,
+                sender.key,
+                &[],
+                sol_dividend_tax,
+            )?,
+            &[
+                source.clone(),
+                dividend_tax_account.clone(),
+                sender.clone(),
+                token_program.clone(),
+            ],
+        )?;
+    }
+    
+    // Transfer remaining tokens to destination
+    invoke(
+        &token_instruction::transfer(
+            token_program.key,
+            source.key,
+            destination.key,
+            sender.key,
+            &[],
+            split.transfer_amount,
+        )?,
+        &[
+            source.clone(),
+            destination.clone(),
+            sender.clone(),
+            token_program.clone(),
+        ],
+    )?;
+    
+    // Settle both sides' reward positions on the balances held up to now,
+    // and count their new balances from here on
+    let source_balance = TokenAccount::unpack(&source.data.borrow())?.amount;
+    let destination_balance = TokenAccount::unpack(&destination.data.borrow())?.amount;
+    for (stream, stream_group) in &mut streams {
+        settle_reward_position(program_id, stream, stream_group[0].key, source.key, stream_group[2], source_data.amount, source_balance)?;
+        if destination.key != source.key {
+            settle_reward_position(
+                program_id,
+                stream,
+                stream_group[0].key,
+                destination.key,
+                stream_group[3],
+                destination_data.amount,
+                destination_balance,
+            )?;
+        }
+        stream.serialize(&mut *stream_group[0].data.borrow_mut())?;
+    }
+    
+    // Any transfer out partially resets the sender's coin age; tokens
+    // received start at zero coin age
+    if let Some(holder_record) = holder_record.as_mut() {
+        holder_record.reset_coin_age_on_sell(source_data.amount, amount, clock.unix_timestamp);
+        holder_record.track_balance(&mut state, source_data.amount, source_data.amount - amount, clock.unix_timestamp);
+    }
+    let mut destination_record = if destination.key == source.key {
+        None
+    } else if destination_record_account.data_is_empty()
+        && state.earns_dividends(destination.key)
+        && !streams.iter().any(|(stream, _)| stream.holds(destination.key))
+    {
+        // Recipients get a holder record on first receipt, paid for by the
+        // sender, so every holder is counted in the total weight. Program
+        // and DEX token accounts earn nothing, and get none.
+        Some(create_holder_record(
+            program_id,
+            sender,
+            state_account,
+            destination,
+            destination_record_account,
+            system_program_account,
+            state.distribution_epoch,
+        )?)
+    } else {
+        load_holder_record(program_id, state_account.key, destination.key, destination_record_account)?
+    };
+    if let Some(destination_record) = destination_record.as_mut() {
+        destination_record.dilute_coin_age_on_receive(destination_data.amount, split.transfer_amount, clock.unix_timestamp);
+        destination_record.track_balance(
+            &mut state,
+            destination_data.amount,
+            destination_data.amount + split.transfer_amount,
+            clock.unix_timestamp,
+        );
+    }
+    
+    // Save updated state
+    state.serialize(&mut *state_account.data.borrow_mut())?;
+    if let Some(holder_record) = holder_record {
+        holder_record.serialize(&mut *holder_record_account.data.borrow_mut())?;
+    }
+    if let Some(destination_record) = destination_record {
+        destination_record.serialize(&mut *destination_record_account.data.borrow_mut())?;
+    }
+    
+    msg!(
+        "Transferred {} tokens with tax: {} LP, {} dividend, {} burned, {} treasury",
+        split.transfer_amount,
+        split.lp,
+        split.dividend,
+        split.burn,
+        split.treasury,
+    );
+    
+    Ok(())
+}
+
+// Distribute dividends to token holders
+fn process_distribute_dividends(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let distributor = next_account_info(account_info_iter)?;
+    let dividend_pool = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let round_account = next_account_info(account_info_iter)?;
+    let clock_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !distributor.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify distribution round account
+    let (round_address, round_bump) = find_distribution_round_address(program_id, state_account.key);
+    if *round_account.key != round_address {
+        return Err(SolDripError::InvalidDistributionRound.into());
+    }
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if state.paused {
+        return Err(SolDripError::ProgramPaused.into());
+    }
+    
+    // Verify dividend pool
+    if *dividend_pool.key != state.dividend_pool {
+        return Err(SolDripError::InvalidDividendPool.into());
+    }
+    let pool_seeds: &[&[u8]] = &[DIVIDEND_POOL_SEED, state_account.key.as_ref(), &[state.dividend_pool_bump]];
+    
+    // Get clock
+    let clock = Clock::from_account_info(clock_account)?;
+    
+    // Create the round account on first use
+    if round_account.data_is_empty() {
+        let rent = Rent::get()?;
+        invoke_signed(
+            &system_instruction::create_account(
+                distributor.key,
+                round_account.key,
+                rent.minimum_balance(DistributionRound::LEN),
+                DistributionRound::LEN as u64,
+                program_id,
+            ),
+            &[distributor.clone(), round_account.clone(), system_program_account.clone()],
+            &[&[DISTRIBUTION_ROUND_SEED, state_account.key.as_ref(), &[round_bump]]],
+        )?;
+        
+        DistributionRound {
+            state: *state_account.key,
+            round_id: 0,
+            is_active: false,
+            distributable_lamports: 0,
+            total_supply: 0,
+            total_weight: 0,
+            distributed_weight: 0,
+            distributed_lamports: 0,
+            crank_reward_lamports: 0,
+            crank_rewards_paid: 0,
+            holders_paid: 0,
+            eligible_supply: 0,
+            started_at: 0,
+            started_by: Pubkey::default(),
+            bump: round_bump,
+        }
+        .serialize(&mut *round_account.data.borrow_mut())?;
+    }
+    
+    let mut round = DistributionRound::try_from_slice(&round_account.data.borrow())?;
+    
+    // Start a new round, snapshotting the pool and the denominator
+    if !round.is_active {
+        // Check the minimum interval, and that the dividend pool has enough
+        // SOL unless the last round was long enough ago to force one
+        state.config.distribution_due(
+            state.pool_balance,
+            state.last_distribution_timestamp,
+            clock.unix_timestamp,
+        )?;
+        
+        // Wait out slippage protection, then deactivate it once it has expired
+        if state.slippage_protection_blocks(clock.unix_timestamp) {
+            return Err(SolDripError::SlippageProtectionActive.into());
+        }
+        state.slippage_protection_active = false;
+        
+        round.round_id += 1;
+        state.distribution_epoch += 1;
+        round.is_active = true;
+        // Commit the booked pool balance to this round: distributable
+        // lamports minus gas costs, which fund the crank reward. The rent
+        // reserve and unclaimed accruals are never part of the balance.
+        round.distributable_lamports = state.pool_balance - bps_of(state.pool_balance, GAS_COST_BPS);
+        round.crank_reward_lamports = state.pool_balance - round.distributable_lamports;
+        round.crank_rewards_paid = 0;
+        state.round_lamports = state.pool_balance;
+        state.pool_balance = 0;
+        // Shares are taken of the total weight counted by holder records.
+        // No record is paid more than it counts, so the round can never pay
+        // out more than it holds; weight left unpaid returns to the pool at
+        // completion.
+        round.total_supply = state.circulating_supply();
+        round.total_weight = state.total_weight;
+        round.distributed_weight = 0;
+        round.distributed_lamports = 0;
+        round.holders_paid = 0;
+        round.eligible_supply = 0;
+        round.started_at = clock.unix_timestamp;
+        round.started_by = *distributor.key;
+        
+        state.last_distribution_timestamp = clock.unix_timestamp;
+        
+        msg!("Started distribution round {}: {} SOL distributable",
+             round.round_id,
+             round.distributable_lamports as f64 / 1_000_000_000.0);
+    }
+    
+    // Get token holder accounts
+    let token_holders: Vec<&AccountInfo> = account_info_iter.collect();
+    let holder_groups = token_holders.chunks_exact(3);
+    if !holder_groups.remainder().is_empty() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    
+    // Derived once, rather than for every holder
+    let exclusions: Vec<Pubkey> = state.dividend_exclusions().collect();
+    
+    // Track total distributed and holders paid in this call
+    let mut total_distributed: u64 = 0;
+    let mut holders_paid: u32 = 0;
+    
+    // Distribute to each token holder
+    for holder_accounts in holder_groups {
+        let (token_holder, holder_record_account, recipient) =
+            (holder_accounts[0], holder_accounts[1], holder_accounts[2]);
+        
+        // Token accounts without a holder record are not counted in the
+        // total weight, and earn nothing
+        let mut holder_record = match load_holder_record(program_id, state_account.key, token_holder.key, holder_record_account)? {
+            Some(holder_record) => holder_record,
+            None => continue,
+        };
+        
+        // A token account that was closed, no longer holds SolDrip tokens,
+        // or was excluded from dividends stops counting in the total weight
+        let token_data = match is_token_account(token_holder, token_program.key) && !exclusions.contains(token_holder.key) {
+            true => TokenAccount::unpack(&token_holder.data.borrow()).ok().filter(|data| data.mint == state.mint),
+            false => None,
+        };
+        let token_data = match token_data {
+            Some(token_data) => token_data,
+            None => {
+                let multiplier_bps = holder_record.weight_multiplier_bps;
+                holder_record.reweigh(&mut state, 0, multiplier_bps);
+                holder_record.serialize(&mut *holder_record_account.data.borrow_mut())?;
+                continue;
+            },
+        };
+        
+        // Dividends go to the payout address from the holder record, or the token account owner
+        if *recipient.key != holder_record.payout_recipient(&token_data.owner) {
+            return Err(SolDripError::InvalidPayoutRecipient.into());
+        }
+        
+        // Flag the record before paying so it cannot be paid twice this round
+        holder_record.mark_paid(round.round_id)?;
+        
+        // Calculate dividend amount from the weight counted in the holder record
+        let (eligible_balance, weight) = holder_record.payout_weight(&state.config, state.distribution_epoch, token_data.amount);
+        let weight = weight.min(round.total_weight - round.distributed_weight);
+        let dividend_amount = calculate_dividend_share(weight, round.total_weight, round.distributable_lamports);
+        round.distributed_weight += weight;
+        
+        if dividend_amount > 0 {
+            match holder_record.compound {
+                true => {
+                    // Compounding holders keep their share in the pool until they claim it
+                    holder_record.accrued_lamports += dividend_amount;
+                    state.reserved_lamports += dividend_amount;
+                    
+                    msg!("Credited {} SOL to compounding token holder {}",
+                         dividend_amount as f64 / 1_000_000_000.0,
+                         token_holder.key);
+                },
+                false => {
+                    // Transfer dividend as SOL from the pool PDA
+                    invoke_signed(
+                        &system_instruction::transfer(dividend_pool.key, recipient.key, dividend_amount),
+                        &[dividend_pool.clone(), recipient.clone(), system_program_account.clone()],
+                        &[pool_seeds],
+                    )?;
+                    
+                    msg!("Distributed {} SOL to token holder {} ({})", 
+                         dividend_amount as f64 / 1_000_000_000.0, 
+                         token_holder.key,
+                         recipient.key);
+                },
+            }
+            
+            round.distributed_lamports += dividend_amount;
+            round.holders_paid += 1;
+            round.eligible_supply += eligible_balance;
+            total_distributed += dividend_amount;
+            holders_paid += 1;
+        }
+        
+        // Observe the live balance, which also picks up changes made outside
+        // TransferWithTax, and count it at the current coin age from the
+        // next round on
+        holder_record.track_balance(&mut state, token_data.amount, token_data.amount, clock.unix_timestamp);
+        holder_record.serialize(&mut *holder_record_account.data.borrow_mut())?;
+    }
+    
+    // Pay the distributor from the gas reserve for each holder this call paid
+    let crank_reward = round.crank_reward(holders_paid);
+    
+    if crank_reward > 0 {
+        invoke_signed(
+            &system_instruction::transfer(dividend_pool.key, distributor.key, crank_reward),
+            &[dividend_pool.clone(), distributor.clone(), system_program_account.clone()],
+            &[pool_seeds],
+        )?;
+        round.crank_rewards_paid += crank_reward;
+        
+        msg!("Paid {} SOL crank reward to {}", crank_reward as f64 / 1_000_000_000.0, distributor.key);
+    }
+    
+    // Update state
+    state.total_sol_distributed += total_distributed;
+    state.round_lamports -= total_distributed + crank_reward;
+    state.serialize(&mut *state_account.data.borrow_mut())?;
+    round.serialize(&mut *round_account.data.borrow_mut())?;
+    
+    msg!("Round {}: distributed {} SOL in this call, {} SOL total",
+         round.round_id,
+         total_distributed as f64 / 1_000_000_000.0,
+         round.distributed_lamports as f64 / 1_000_000_000.0);
+    
+    Ok(())
+}
+
+// Append a summary to the distribution history, creating the history
+// account on first use
+fn record_distribution_history<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    state_account: &AccountInfo<'a>,
+    history_account: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+    summary: RoundSummary,
+) -> ProgramResult {
+    // Verify the history account
+    let (history_address, history_bump) = find_distribution_history_address(program_id, state_account.key);
+    if *history_account.key != history_address {
+        return Err(SolDripError::InvalidDistributionHistory.into());
+    }
+    if history_account.data_is_empty() {
+        let rent = Rent::get()?;
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                history_account.key,
+                rent.minimum_balance(DistributionHistory::LEN),
+                DistributionHistory::LEN as u64,
+                program_id,
+            ),
+            &[payer.clone(), history_account.clone(), system_program_account.clone()],
+            &[&[DISTRIBUTION_HISTORY_SEED, state_account.key.as_ref(), &[history_bump]]],
+        )?;
+        
+        DistributionHistory {
+            state: *state_account.key,
+            total_rounds: 0,
+            entries: [RoundSummary::default(); DISTRIBUTION_HISTORY_CAPACITY],
+            bump: history_bump,
+        }
+        .serialize(&mut *history_account.data.borrow_mut())?;
+    }
+    if history_account.owner != program_id {
+        return Err(SolDripError::InvalidDistributionHistory.into());
+    }
+    
+    let mut history = DistributionHistory::try_from_slice(&history_account.data.borrow())?;
+    history.record(summary);
+    history.serialize(&mut *history_account.data.borrow_mut())?;
+    
+    Ok(())
+}
+
+// Close the current distribution round
+fn process_complete_distribution_round(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let caller = next_account_info(account_info_iter)?;
+    let round_account = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let clock_account = next_account_info(account_info_iter)?;
+    let history_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !caller.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify distribution round account
+    let (round_address, _) = find_distribution_round_address(program_id, state_account.key);
+    if *round_account.key != round_address || round_account.owner != program_id {
+        return Err(SolDripError::InvalidDistributionRound.into());
+    }
+    
+    let mut round = DistributionRound::try_from_slice(&round_account.data.borrow())?;
+    if !round.is_active {
+        return Err(SolDripError::NoActiveDistributionRound.into());
+    }
+    
+    // Only the starter may close a round early; anyone may close a stale one
+    let clock = Clock::from_account_info(clock_account)?;
+    if *caller.key != round.started_by
+        && clock.unix_timestamp - round.started_at < DISTRIBUTION_ROUND_TIMEOUT
+    {
+        return Err(SolDripError::DistributionRoundInProgress.into());
+    }
+    
+    round.is_active = false;
+    round.serialize(&mut *round_account.data.borrow_mut())?;
+    
+    // Return whatever the round did not pay out to the pool balance: unpaid
+    // dividends and the unused part of the crank reserve
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    let summary = round.summary(clock.unix_timestamp);
+    let unspent = state.round_lamports;
+    state.pool_balance += unspent;
+    state.round_lamports = 0;
+    state.serialize(&mut *state_account.data.borrow_mut())?;
+    
+    record_distribution_history(program_id, caller, state_account, history_account, system_program_account, summary)?;
+    
+    msg!("Completed distribution round {}: {} SOL to {} holders, {} SOL returned to the pool ({} SOL unused crank reserve)",
+         round.round_id,
+         round.distributed_lamports as f64 / 1_000_000_000.0,
+         round.holders_paid,
+         unspent as f64 / 1_000_000_000.0,
+         summary.crank_reserve_returned as f64 / 1_000_000_000.0);
+    
+    Ok(())
+}
+
+// Publish a Merkle distribution funded from the pool balance
+fn process_publish_merkle_root(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    merkle_root: [u8; 32],
+    total_amount: u64,
+    claim_count: u32,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let publisher = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let distribution_account = next_account_info(account_info_iter)?;
+    let round_account = next_account_info(account_info_iter)?;
+    let clock_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    let history_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !publisher.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if state.paused {
+        return Err(SolDripError::ProgramPaused.into());
+    }
+    if !state.can_publish_merkle_root(publisher.key) {
+        return Err(SolDripError::Unauthorized.into());
+    }
+    
+    if claim_count == 0 || total_amount == 0 {
+        return Err(SolDripError::InvalidMerkleDistribution.into());
+    }
+    if total_amount > state.pool_balance {
+        return Err(SolDripError::InsufficientSolForDistribution.into());
+    }
+    
+    // A distribution round in progress owns the current epoch
+    let (round_address, _) = find_distribution_round_address(program_id, state_account.key);
+    if *round_account.key != round_address {
+        return Err(SolDripError::InvalidDistributionRound.into());
+    }
+    if !round_account.data_is_empty() {
+        if round_account.owner != program_id {
+            return Err(SolDripError::InvalidDistributionRound.into());
+        }
+        if DistributionRound::try_from_slice(&round_account.data.borrow())?.is_active {
+            return Err(SolDripError::DistributionRoundInProgress.into());
+        }
+    }
+    
+    // Same cadence as distribution rounds
+    let clock = Clock::from_account_info(clock_account)?;
+    state.config.distribution_due(
+        state.pool_balance,
+        state.last_distribution_timestamp,
+        clock.unix_timestamp,
+    )?;
+    if state.slippage_protection_blocks(clock.unix_timestamp) {
+        return Err(SolDripError::SlippageProtectionActive.into());
+    }
+    state.slippage_protection_active = false;
+    
+    // Create the distribution account
+    let distribution_id = state.merkle_distribution_count + 1;
+    let (distribution_address, distribution_bump) =
+        find_merkle_distribution_address(program_id, state_account.key, distribution_id);
+    if *distribution_account.key != distribution_address || !distribution_account.data_is_empty() {
+        return Err(SolDripError::InvalidMerkleDistribution.into());
+    }
+    let rent = Rent::get()?;
+    invoke_signed(
+        &system_instruction::create_account(
+            publisher.key,
+            distribution_account.key,
+            rent.minimum_balance(MerkleDistribution::LEN),
+            MerkleDistribution::LEN as u64,
+            program_id,
+        ),
+        &[publisher.clone(), distribution_account.clone(), system_program_account.clone()],
+        &[&[MERKLE_DISTRIBUTION_SEED, state_account.key.as_ref(), &distribution_id.to_le_bytes(), &[distribution_bump]]],
+    )?;
+    
+    state.merkle_distribution_count = distribution_id;
+    state.distribution_epoch += 1;
+    state.last_distribution_timestamp = clock.unix_timestamp;
+    
+    let distribution = MerkleDistribution {
+        state: *state_account.key,
+        distribution_id,
+        epoch: state.distribution_epoch,
+        merkle_root,
+        total_amount,
+        claimed_amount: 0,
+        claim_count,
+        claims_made: 0,
+        published_at: clock.unix_timestamp,
+        publisher: *publisher.key,
+        bump: distribution_bump,
+        expires_at: clock.unix_timestamp + MERKLE_CLAIM_PERIOD,
+        reclaimed_amount: 0,
+        reclaimed: false,
+    };
+    distribution.serialize(&mut *distribution_account.data.borrow_mut())?;
+    
+    // Create one claim bitmap per MERKLE_CLAIMS_PER_BITMAP leaves
+    let bitmap_accounts: Vec<&AccountInfo> = account_info_iter.collect();
+    if bitmap_accounts.len() != distribution.bitmap_count() as usize {
+        return Err(SolDripError::InvalidClaimBitmap.into());
+    }
+    for (bitmap_index, bitmap_account) in (0u32..).zip(bitmap_accounts) {
+        let (bitmap_address, bitmap_bump) =
+            find_merkle_claim_bitmap_address(program_id, distribution_account.key, bitmap_index);
+        if *bitmap_account.key != bitmap_address || !bitmap_account.data_is_empty() {
+            return Err(SolDripError::InvalidClaimBitmap.into());
+        }
+        invoke_signed(
+            &system_instruction::create_account(
+                publisher.key,
+                bitmap_account.key,
+                rent.minimum_balance(MerkleClaimBitmap::LEN),
+                MerkleClaimBitmap::LEN as u64,
+                program_id,
+            ),
+            &[publisher.clone(), bitmap_account.clone(), system_program_account.clone()],
+            &[&[MERKLE_CLAIM_BITMAP_SEED, distribution_account.key.as_ref(), &bitmap_index.to_le_bytes(), &[bitmap_bump]]],
+        )?;
+        
+        MerkleClaimBitmap {
+            distribution: *distribution_account.key,
+            bitmap_index,
+            bump: bitmap_bump,
+        }
+        .serialize(&mut *bitmap_account.data.borrow_mut())?;
+    }
+    
+    // The lamports stay in the dividend pool, reserved until claimed
+    state.pool_balance -= total_amount;
+    state.reserved_lamports += total_amount;
+    state.serialize(&mut *state_account.data.borrow_mut())?;
+    
+    record_distribution_history(
+        program_id,
+        publisher,
+        state_account,
+        history_account,
+        system_program_account,
+        distribution.summary(),
+    )?;
+    
+    msg!("Published Merkle distribution {}: {} SOL to {} holders",
+         distribution_id,
+         total_amount as f64 / 1_000_000_000.0,
+         claim_count);
+    
+    Ok(())
+}
+
+// Claim a holder's share of a Merkle distribution
+fn process_claim_merkle_dividend(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    index: u32,
+    amount: u64,
+    proof: &[[u8; 32]],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let holder = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let distribution_account = next_account_info(account_info_iter)?;
+    let bitmap_account = next_account_info(account_info_iter)?;
+    let dividend_pool = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !holder.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if state.paused {
+        return Err(SolDripError::ProgramPaused.into());
+    }
+    if *dividend_pool.key != state.dividend_pool {
+        return Err(SolDripError::InvalidDividendPool.into());
+    }
+    let pool_seeds: &[&[u8]] = &[DIVIDEND_POOL_SEED, state_account.key.as_ref(), &[state.dividend_pool_bump]];
+    
+    // Verify the distribution and the bitmap covering this leaf
+    if distribution_account.owner != program_id {
+        return Err(SolDripError::InvalidMerkleDistribution.into());
+    }
+    let mut distribution = MerkleDistribution::try_from_slice(&distribution_account.data.borrow())?;
+    if distribution.state != *state_account.key {
+        return Err(SolDripError::InvalidMerkleDistribution.into());
+    }
+    if index >= distribution.claim_count {
+        return Err(SolDripError::InvalidMerkleProof.into());
+    }
+    let clock = Clock::get()?;
+    if distribution.is_expired(clock.unix_timestamp) {
+        return Err(SolDripError::MerkleDistributionExpired.into());
+    }
+    let (bitmap_address, _) =
+        find_merkle_claim_bitmap_address(program_id, distribution_account.key, index / MERKLE_CLAIMS_PER_BITMAP);
+    if *bitmap_account.key != bitmap_address || bitmap_account.owner != program_id {
+        return Err(SolDripError::InvalidClaimBitmap.into());
+    }
+    
+    if !verify_merkle_proof(&distribution.merkle_root, merkle_leaf(index, holder.key, amount), proof) {
+        return Err(SolDripError::InvalidMerkleProof.into());
+    }
+    
+    // Flag the leaf before paying so it cannot be claimed twice
+    if MerkleClaimBitmap::is_claimed(&bitmap_account.data.borrow(), index) {
+        return Err(SolDripError::AlreadyClaimed.into());
+    }
+    MerkleClaimBitmap::set_claimed(&mut bitmap_account.data.borrow_mut(), index);
+    
+    // A root whose leaves add up to more than it was funded with cannot
+    // pay out more than its reserve
+    distribution.claimed_amount = distribution
+        .claimed_amount
+        .checked_add(amount)
+        .filter(|claimed| *claimed <= distribution.total_amount)
+        .ok_or(SolDripError::InvalidMerkleDistribution)?;
+    distribution.claims_made += 1;
+    distribution.serialize(&mut *distribution_account.data.borrow_mut())?;
+    
+    state.reserved_lamports = state.reserved_lamports.saturating_sub(amount);
+    state.total_sol_distributed += amount;
+    state.serialize(&mut *state_account.data.borrow_mut())?;
+    
+    invoke_signed(
+        &system_instruction::transfer(dividend_pool.key, holder.key, amount),
+        &[dividend_pool.clone(), holder.clone(), system_program_account.clone()],
+        &[pool_seeds],
+    )?;
+    
+    msg!("Claimed {} SOL from Merkle distribution {} for {}",
+         amount as f64 / 1_000_000_000.0,
+         distribution.distribution_id,
+         holder.key);
+    
+    Ok(())
+}
+
+// Return the unclaimed part of an expired Merkle distribution to the pool balance
+fn process_reclaim_merkle_distribution(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let authority = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let distribution_account = next_account_info(account_info_iter)?;
+    let clock_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if *authority.key != state.authority {
+        return Err(SolDripError::Unauthorized.into());
+    }
+    
+    // Verify the distribution
+    if distribution_account.owner != program_id {
+        return Err(SolDripError::InvalidMerkleDistribution.into());
+    }
+    let mut distribution = MerkleDistribution::try_from_slice(&distribution_account.data.borrow())?;
+    if distribution.state != *state_account.key {
+        return Err(SolDripError::InvalidMerkleDistribution.into());
+    }
+    let clock = Clock::from_account_info(clock_account)?;
+    if !distribution.is_expired(clock.unix_timestamp) {
+        return Err(SolDripError::MerkleDistributionNotExpired.into());
+    }
+    if distribution.reclaimed {
+        return Err(SolDripError::AlreadyClaimed.into());
+    }
+    
+    // The lamports never left the dividend pool, so only the books move
+    let unclaimed = distribution.unclaimed_amount();
+    distribution.reclaimed_amount = unclaimed;
+    distribution.reclaimed = true;
+    distribution.serialize(&mut *distribution_account.data.borrow_mut())?;
+    
+    state.reserved_lamports = state.reserved_lamports.saturating_sub(unclaimed);
+    state.pool_balance += unclaimed;
+    state.serialize(&mut *state_account.data.borrow_mut())?;
+    
+    msg!("Reclaimed {} SOL from Merkle distribution {}",
+         unclaimed as f64 / 1_000_000_000.0,
+         distribution.distribution_id);
+    
+    Ok(())
+}
+
+// Create the holder record for a token account
+fn process_initialize_holder(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let payer = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let token_holder = next_account_info(account_info_iter)?;
+    let holder_record_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the token account holds SolDrip tokens
+    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if state_account.owner != program_id || !is_token_account(token_holder, &spl_token::id()) {
+        return Err(SolDripError::InvalidTokenAccount.into());
+    }
+    let token_data = TokenAccount::unpack(&token_holder.data.borrow())?;
+    if token_data.mint != state.mint {
+        return Err(SolDripError::InvalidTokenAccount.into());
+    }
+    
+    // Program and DEX token accounts earn nothing
+    let stream_accounts: Vec<&AccountInfo> = account_info_iter.collect();
+    let streams = load_reward_streams(program_id, &state, state_account.key, &stream_accounts)?;
+    if !state.earns_dividends(token_holder.key) || streams.iter().any(|stream| stream.holds(token_holder.key)) {
+        return Err(SolDripError::ExcludedFromDividends.into());
+    }
+    
+    // Create holder record
+    let mut holder_record = create_holder_record(
+        program_id,
+        payer,
+        state_account,
+        token_holder,
+        holder_record_account,
+        system_program_account,
+        state.distribution_epoch,
+    )?;
+    
+    // Count the balance in the total weight at zero coin age
+    let multiplier_bps = state.config.holding_multiplier_bps(Some(0));
+    holder_record.reweigh(&mut state, token_data.amount, multiplier_bps);
+    holder_record.serialize(&mut *holder_record_account.data.borrow_mut())?;
+    state.serialize(&mut *state_account.data.borrow_mut())?;
+    
+    msg!("Initialized holder record for token account {}", token_holder.key);
+    
+    Ok(())
+}
+
+// Set the wallet that receives a token account's dividends
+fn process_set_payout_address(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    payout_address: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let owner = next_account_info(account_info_iter)?;
+    let token_holder = next_account_info(account_info_iter)?;
+    let holder_record_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Only the current token account owner may redirect its dividends
+    let (_, mut holder_record) = load_owned_holder_record(program_id, owner, token_holder, holder_record_account)?;
+    
+    holder_record.payout_address = payout_address;
+    holder_record.serialize(&mut *holder_record_account.data.borrow_mut())?;
+    
+    msg!("Payout address for token account {} set to {}", token_holder.key, payout_address);
+    
+    Ok(())
+}
+
+// Opt a token account in or out of auto-compounding
+fn process_set_compounding(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    enabled: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let owner = next_account_info(account_info_iter)?;
+    let token_holder = next_account_info(account_info_iter)?;
+    let holder_record_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    let (_, mut holder_record) = load_owned_holder_record(program_id, owner, token_holder, holder_record_account)?;
+    
+    holder_record.compound = enabled;
+    holder_record.serialize(&mut *holder_record_account.data.borrow_mut())?;
+    
+    msg!("Compounding for token account {} set to {}", token_holder.key, enabled);
+    
+    Ok(())
+}
+
+// Claim dividends accrued in a holder record, as SOL or compounded into DRIP
+fn process_claim_dividends(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    minimum_amount_out: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let owner = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let dividend_pool = next_account_info(account_info_iter)?;
+    let token_holder = next_account_info(account_info_iter)?;
+    let holder_record_account = next_account_info(account_info_iter)?;
+    let recipient = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if state.paused {
+        return Err(SolDripError::ProgramPaused.into());
+    }
+    if *dividend_pool.key != state.dividend_pool {
+        return Err(SolDripError::InvalidDividendPool.into());
+    }
+    let pool_seeds: &[&[u8]] = &[DIVIDEND_POOL_SEED, state_account.key.as_ref(), &[state.dividend_pool_bump]];
+    
+    let (token_data, mut holder_record) = load_owned_holder_record(program_id, owner, token_holder, holder_record_account)?;
+    if holder_record.state != *state_account.key {
+        return Err(SolDripError::InvalidHolderRecord.into());
+    }
+    
+    let claim_amount = holder_record.accrued_lamports;
+    if claim_amount == 0 {
+        return Err(SolDripError::NothingToClaim.into());
+    }
+    
+    // Clear the accrual before any CPI
+    holder_record.accrued_lamports = 0;
+    holder_record.serialize(&mut *holder_record_account.data.borrow_mut())?;
+    state.reserved_lamports = state.reserved_lamports.saturating_sub(claim_amount);
+    state.serialize(&mut *state_account.data.borrow_mut())?;
+    
+    if holder_record.compound {
+        // Compound: swap the SOL into DRIP, deposited straight into the
+        // holder's token account so no transfer tax applies
+        let swap_program = next_account_info(account_info_iter)?;
+        if state.config.swap_program == Pubkey::default() || *swap_program.key != state.config.swap_program {
+            return Err(SolDripError::SwapAdapterNotConfigured.into());
+        }
+        let swap_accounts: Vec<AccountInfo> = account_info_iter.cloned().collect();
+        
+        // The pool signs the swap, so keep the token accounts it holds out of reach
+        let pool_token_accounts = [state.lp_pool, state.dividend_tax_account()];
+        if swap_accounts.iter().any(|account| pool_token_accounts.contains(account.key)) {
+            return Err(SolDripError::InvalidSwapAccount.into());
+        }
+        
+        let received = swap_sol_for_tokens(
+            swap_program,
+            dividend_pool,
+            token_holder,
+            &swap_accounts,
+            claim_amount,
+            minimum_amount_out,
+            pool_seeds,
+        )?;
+        
+        // Compounded tokens are exempt from the maximum holding up to the compound cap
+        if token_data.amount.saturating_add(received) > state.compound_holding_limit() {
+            return Err(SolDripError::ExceedsMaximumHolding.into());
+        }
+        
+        msg!("Compounded {} SOL into {} tokens for token holder {}",
+             claim_amount as f64 / 1_000_000_000.0,
+             received,
+             token_holder.key);
+    } else {
+        // Pay the accrued SOL to the holder's payout recipient
+        if *recipient.key != holder_record.payout_recipient(&token_data.owner) {
+            return Err(SolDripError::InvalidPayoutRecipient.into());
+        }
+        
+        invoke_signed(
+            &system_instruction::transfer(dividend_pool.key, recipient.key, claim_amount),
+            &[dividend_pool.clone(), recipient.clone(), system_program_account.clone()],
+            &[pool_seeds],
+        )?;
+        
+        msg!("Claimed {} SOL for token holder {} ({})",
+             claim_amount as f64 / 1_000_000_000.0,
+             token_holder.key,
+             recipient.key);
+    }
+    
+    Ok(())
+}
+
+// Update the program configuration
+fn process_update_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: SolDripConfig,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let authority = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if state.authority != *authority.key {
+        return Err(SolDripError::Unauthorized.into());
+    }
+    config.validate()?;
+    
+    state.config = config;
+    state.serialize(&mut *state_account.data.borrow_mut())?;
+    
+    msg!("Config updated by {}", authority.key);
+    
+    Ok(())
+}
+
+// Open trading
+fn process_enable_trading(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let authority = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let clock_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if state.authority != *authority.key {
+        return Err(SolDripError::Unauthorized.into());
+    }
+    if state.trading_enabled {
+        return Err(SolDripError::TradingAlreadyEnabled.into());
+    }
+    
+    let clock = Clock::from_account_info(clock_account)?;
+    state.trading_enabled = true;
+    state.launch_slot = clock.slot;
+    state.serialize(&mut *state_account.data.borrow_mut())?;
+    
+    msg!("Trading enabled at slot {}", clock.slot);
+    
+    Ok(())
+}
+
+// Pause or resume the program
+fn process_set_paused(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    paused: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let authority = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if state.authority != *authority.key {
+        return Err(SolDripError::Unauthorized.into());
+    }
+    
+    state.paused = paused;
+    state.serialize(&mut *state_account.data.borrow_mut())?;
+    
+    msg!("Program {}", if paused { "paused" } else { "resumed" });
+    
+    Ok(())
+}
+
+// Exempt a token account from transaction limits
+fn process_set_limit_exemption(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    exempt: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let authority = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let token_holder = next_account_info(account_info_iter)?;
+    let holder_record_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if state.authority != *authority.key {
+        return Err(SolDripError::Unauthorized.into());
+    }
+    
+    let mut holder_record = load_holder_record(program_id, state_account.key, token_holder.key, holder_record_account)?
+        .ok_or(SolDripError::InvalidHolderRecord)?;
+    holder_record.exempt_from_limits = exempt;
+    holder_record.serialize(&mut *holder_record_account.data.borrow_mut())?;
+    
+    msg!("Limit exemption for token account {} set to {}", token_holder.key, exempt);
+    
+    Ok(())
+}
+
+// Deposit SOL into the dividend pool
+fn process_deposit_dividends(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let depositor = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let dividend_pool = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !depositor.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if *dividend_pool.key != state.dividend_pool {
+        return Err(SolDripError::InvalidDividendPool.into());
+    }
+    
+    invoke(
+        &system_instruction::transfer(depositor.key, dividend_pool.key, amount),
+        &[depositor.clone(), dividend_pool.clone(), system_program_account.clone()],
+    )?;
+    
+    state.pool_balance += amount;
+    state.serialize(&mut *state_account.data.borrow_mut())?;
+    
+    msg!("Deposited {} SOL into the dividend pool", amount as f64 / 1_000_000_000.0);
+    
+    Ok(())
+}
+
+// Book unexpected dividend pool lamports as a donation
+fn process_sync_pool(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let state_account = next_account_info(account_info_iter)?;
+    let dividend_pool = next_account_info(account_info_iter)?;
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if *dividend_pool.key != state.dividend_pool {
+        return Err(SolDripError::InvalidDividendPool.into());
+    }
+    
+    // Everything above the rent reserve and the booked balances is a donation
+    let rent_reserve = Rent::get()?.minimum_balance(0);
+    let booked = rent_reserve + state.booked_pool_lamports();
+    let pool_lamports = dividend_pool.lamports();
+    if pool_lamports < booked {
+        return Err(SolDripError::PoolBalanceMismatch.into());
+    }
+    
+    let donation = pool_lamports - booked;
+    state.pool_balance += donation;
+    state.total_donations += donation;
+    state.serialize(&mut *state_account.data.borrow_mut())?;
+    
+    msg!("Booked {} SOL donation to the dividend pool", donation as f64 / 1_000_000_000.0);
+    
+    Ok(())
+}
+
+// Create a reward stream paying holders in an SPL token
+fn process_create_reward_stream(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    tax_share_bps: u16,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let authority = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let stream_account = next_account_info(account_info_iter)?;
+    let reward_mint = next_account_info(account_info_iter)?;
+    let vault = next_account_info(account_info_iter)?;
+    let tax_account = next_account_info(account_info_iter)?;
+    let mint_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if state.authority != *authority.key {
+        return Err(SolDripError::Unauthorized.into());
+    }
+    if *mint_account.key != state.mint || *token_program.key != spl_token::id() {
+        return Err(SolDripError::InvalidTokenAccount.into());
+    }
+    
+    // Stream shares come out of the SOL dividend tax, so they cannot exceed it
+    let total_share = state.reward_stream_tax_share_bps as u64 + tax_share_bps as u64;
+    if total_share > BPS_DENOMINATOR {
+        return Err(SolDripError::RewardStreamShareTooHigh.into());
+    }
+    
+    // Verify PDAs
+    let (stream_address, stream_bump) = find_reward_stream_address(program_id, state_account.key, reward_mint.key);
+    let (vault_address, vault_bump) = find_reward_vault_address(program_id, &stream_address);
+    let (tax_address, tax_bump) = find_reward_tax_address(program_id, &stream_address);
+    if *stream_account.key != stream_address || *vault.key != vault_address || *tax_account.key != tax_address {
+        return Err(SolDripError::InvalidRewardStream.into());
+    }
+    
+    let rent = Rent::get()?;
+    let stream_seeds: &[&[u8]] = &[REWARD_STREAM_SEED, state_account.key.as_ref(), reward_mint.key.as_ref(), &[stream_bump]];
+    
+    // Create stream account
+    invoke_signed(
+        &system_instruction::create_account(
+            authority.key,
+            stream_account.key,
+            rent.minimum_balance(RewardStream::LEN),
+            RewardStream::LEN as u64,
+            program_id,
+        ),
+        &[authority.clone(), stream_account.clone(), system_program_account.clone()],
+        &[stream_seeds],
+    )?;
+    
+    // Create the reward vault and DRIP tax account, both owned by the stream PDA
+    for (token_account, mint, seeds) in [
+        (vault, reward_mint, &[REWARD_VAULT_SEED, stream_address.as_ref(), &[vault_bump]] as &[&[u8]]),
+        (tax_account, mint_account, &[REWARD_TAX_SEED, stream_address.as_ref(), &[tax_bump]] as &[&[u8]]),
+    ] {
+        invoke_signed(
+            &system_instruction::create_account(
+                authority.key,
+                token_account.key,
+                rent.minimum_balance(TokenAccount::LEN),
+                TokenAccount::LEN as u64,
+                token_program.key,
+            ),
+            &[authority.clone(), token_account.clone(), system_program_account.clone()],
+            &[seeds],
+        )?;
+        
+        invoke(
+            &token_instruction::initialize_account3(
+                token_program.key,
+                token_account.key,
+                mint.key,
+                stream_account.key,
+            )?,
+            &[token_account.clone(), mint.clone(), token_program.clone()],
+        )?;
+    }
+    
+    RewardStream {
+        state: *state_account.key,
+        reward_mint: *reward_mint.key,
+        vault: *vault.key,
+        tax_account: *tax_account.key,
+        tax_share_bps,
+        reward_per_token: 0,
+        total_funded: 0,
+        total_claimed: 0,
+        bump: stream_bump,
+        total_position_balance: 0,
+        unallocated: 0,
+    }
+    .serialize(&mut *stream_account.data.borrow_mut())?;
+    
+    state.reward_stream_count += 1;
+    state.reward_stream_tax_share_bps = total_share as u16;
+    state.serialize(&mut *state_account.data.borrow_mut())?;
+    
+    msg!("Created reward stream for mint {} with {} bps of the dividend tax", reward_mint.key, tax_share_bps);
+    
+    Ok(())
+}
+
+// Deposit reward tokens into a stream and credit them to holders
+fn process_fund_reward_stream(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let funder = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let stream_account = next_account_info(account_info_iter)?;
+    let source = next_account_info(account_info_iter)?;
+    let vault = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !funder.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut stream = load_reward_stream(program_id, state_account.key, stream_account)?;
+    if *vault.key != stream.vault {
+        return Err(SolDripError::InvalidRewardStream.into());
+    }
+    
+    invoke(
+        &token_instruction::transfer(
+            token_program.key,
+            source.key,
+            vault.key,
+            funder.key,
+            &[],
+            amount,
+        )?,
+        &[
+            source.clone(),
+            vault.clone(),
+            funder.clone(),
+            token_program.clone(),
+        ],
+    )?;
+    
+    stream.accrue(amount);
+    stream.serialize(&mut *stream_account.data.borrow_mut())?;
+    
+    msg!("Funded reward stream {} with {} tokens", stream_account.key, amount);
+    
+    Ok(())
+}
+
+// Swap a stream's collected DRIP tax into the reward token
+fn process_convert_stream_tax(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    minimum_amount_out: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let caller = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let stream_account = next_account_info(account_info_iter)?;
+    let tax_account = next_account_info(account_info_iter)?;
+    let vault = next_account_info(account_info_iter)?;
+    let swap_program = next_account_info(account_info_iter)?;
+    let swap_accounts: Vec<AccountInfo> = account_info_iter.cloned().collect();
+    
+    // Verify signer
+    if !caller.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if !state.can_convert_stream_tax(caller.key) {
+        return Err(SolDripError::Unauthorized.into());
+    }
+    if state.config.swap_program == Pubkey::default() || *swap_program.key != state.config.swap_program {
+        return Err(SolDripError::SwapAdapterNotConfigured.into());
+    }
+    
+    // An unbounded swap of the whole tax account could be sandwiched
+    if minimum_amount_out == 0 {
+        return Err(SolDripError::MinimumAmountOutRequired.into());
+    }
+    
+    let mut stream = load_reward_stream(program_id, state_account.key, stream_account)?;
+    if *tax_account.key != stream.tax_account || *vault.key != stream.vault {
+        return Err(SolDripError::InvalidRewardStream.into());
+    }
+    
+    let amount_in = TokenAccount::unpack(&tax_account.data.borrow())?.amount;
+    if amount_in == 0 {
+        return Err(SolDripError::NothingToClaim.into());
+    }
+    
+    let received = swap_tokens_for_tokens(
+        swap_program,
+        stream_account,
+        tax_account,
+        vault,
+        &swap_accounts,
+        amount_in,
+        minimum_amount_out,
+        &[REWARD_STREAM_SEED, state_account.key.as_ref(), stream.reward_mint.as_ref(), &[stream.bump]],
+    )?;
+    
+    stream.accrue(received);
+    stream.serialize(&mut *stream_account.data.borrow_mut())?;
+    
+    msg!("Converted {} DRIP into {} reward tokens for stream {}", amount_in, received, stream_account.key);
+    
+    Ok(())
+}
+
+// Claim everything a token account is owed across reward streams
+fn process_claim_rewards(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let owner = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let token_holder = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if state.paused {
+        return Err(SolDripError::ProgramPaused.into());
+    }
+    
+    // Verify the signer owns a SolDrip token account
+    if !is_token_account(token_holder, token_program.key) {
+        return Err(SolDripError::InvalidTokenAccount.into());
+    }
+    let token_data = TokenAccount::unpack(&token_holder.data.borrow())?;
+    if token_data.mint != state.mint {
+        return Err(SolDripError::InvalidTokenAccount.into());
+    }
+    if token_data.owner != *owner.key {
+        return Err(SolDripError::NotTokenAccountOwner.into());
+    }
+    
+    let stream_accounts: Vec<&AccountInfo> = account_info_iter.collect();
+    let stream_groups = stream_accounts.chunks_exact(4);
+    if !stream_groups.remainder().is_empty() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    
+    let rent = Rent::get()?;
+    for stream_group in stream_groups {
+        let (stream_account, vault, position_account, destination) =
+            (stream_group[0], stream_group[1], stream_group[2], stream_group[3]);
+        
+        let mut stream = load_reward_stream(program_id, state_account.key, stream_account)?;
+        if *vault.key != stream.vault {
+            return Err(SolDripError::InvalidRewardStream.into());
+        }
+        
+        let (position_address, position_bump) = find_reward_position_address(program_id, stream_account.key, token_holder.key);
+        if *position_account.key != position_address {
+            return Err(SolDripError::InvalidRewardStream.into());
+        }
+        
+        // Open the position on the first claim; it accrues from now on
+        if position_account.data_is_empty() {
+            invoke_signed(
+                &system_instruction::create_account(
+                    owner.key,
+                    position_account.key,
+                    rent.minimum_balance(RewardPosition::LEN),
+                    RewardPosition::LEN as u64,
+                    program_id,
+                ),
+                &[owner.clone(), position_account.clone(), system_program_account.clone()],
+                &[&[REWARD_POSITION_SEED, stream_account.key.as_ref(), token_holder.key.as_ref(), &[position_bump]]],
+            )?;
+            
+            RewardPosition {
+                stream: *stream_account.key,
+                token_account: *token_holder.key,
+                reward_per_token_paid: stream.reward_per_token,
+                balance: token_data.amount,
+                accrued: 0,
+                total_claimed: 0,
+                bump: position_bump,
+            }
+            .serialize(&mut *position_account.data.borrow_mut())?;
+            stream.total_position_balance += token_data.amount;
+            stream.serialize(&mut *stream_account.data.borrow_mut())?;
+            
+            msg!("Opened reward position in stream {}", stream_account.key);
+            continue;
+        }
+        
+        let mut position = RewardPosition::try_from_slice(&position_account.data.borrow())?;
+        let vault_balance = TokenAccount::unpack(&vault.data.borrow())?.amount;
+        let pending = position.claim(&mut stream, token_data.amount, vault_balance);
+        position.serialize(&mut *position_account.data.borrow_mut())?;
+        stream.serialize(&mut *stream_account.data.borrow_mut())?;
+        
+        if pending == 0 {
+            continue;
+        }
+        
+        // Rewards go to a token account of the reward mint owned by the holder
+        let destination_data = TokenAccount::unpack(&destination.data.borrow())?;
+        if destination_data.owner != *owner.key || destination_data.mint != stream.reward_mint {
+            return Err(SolDripError::InvalidPayoutRecipient.into());
+        }
+        
+        invoke_signed(
+            &token_instruction::transfer(
+                token_program.key,
+                vault.key,
+                destination.key,
+                stream_account.key,
+                &[],
+                pending,
+            )?,
+            &[
+                vault.clone(),
+                destination.clone(),
+                stream_account.clone(),
+                token_program.clone(),
+            ],
+            &[&[REWARD_STREAM_SEED, state_account.key.as_ref(), stream.reward_mint.as_ref(), &[stream.bump]]],
+        )?;
+        
+        stream.total_claimed += pending;
+        stream.serialize(&mut *stream_account.data.borrow_mut())?;
+        
+        msg!("Claimed {} reward tokens from stream {}", pending, stream_account.key);
+    }
+    
+    Ok(())
+}
+
+// Move a stream's unallocated rewards out of its vault
+fn process_sweep_reward_stream(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let authority = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let stream_account = next_account_info(account_info_iter)?;
+    let vault = next_account_info(account_info_iter)?;
+    let destination = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if state.authority != *authority.key {
+        return Err(SolDripError::Unauthorized.into());
+    }
+    
+    let mut stream = load_reward_stream(program_id, state_account.key, stream_account)?;
+    if *vault.key != stream.vault {
+        return Err(SolDripError::InvalidRewardStream.into());
+    }
+    
+    let vault_balance = TokenAccount::unpack(&vault.data.borrow())?.amount;
+    let amount = stream.unallocated.min(vault_balance);
+    if amount == 0 {
+        return Err(SolDripError::NothingToClaim.into());
+    }
+    
+    invoke_signed(
+        &token_instruction::transfer(
+            token_program.key,
+            vault.key,
+            destination.key,
+            stream_account.key,
+            &[],
+            amount,
+        )?,
+        &[
+            vault.clone(),
+            destination.clone(),
+            stream_account.clone(),
+            token_program.clone(),
+        ],
+        &[&[REWARD_STREAM_SEED, state_account.key.as_ref(), stream.reward_mint.as_ref(), &[stream.bump]]],
+    )?;
+    
+    stream.unallocated -= amount;
+    stream.serialize(&mut *stream_account.data.borrow_mut())?;
+    
+    msg!("Swept {} unallocated reward tokens from stream {}", amount, stream_account.key);
+    
+    Ok(())
+}
+
+// Create the treasury vault
+fn process_initialize_treasury(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let authority = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let treasury_vault = next_account_info(account_info_iter)?;
+    let mint_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if state.authority != *authority.key {
+        return Err(SolDripError::Unauthorized.into());
+    }
+    if *mint_account.key != state.mint || *token_program.key != spl_token::id() {
+        return Err(SolDripError::InvalidTokenAccount.into());
+    }
+    
+    // The per-period withdrawal limit has no unlimited setting; require one
+    if state.config.treasury_withdraw_limit == 0 {
+        return Err(SolDripError::InvalidConfig.into());
+    }
+    
+    // Verify treasury vault PDA
+    let (vault_address, vault_bump) = find_treasury_vault_address(program_id, state_account.key);
+    if *treasury_vault.key != vault_address || state.treasury_vault != Pubkey::default() {
+        return Err(SolDripError::InvalidTreasuryVault.into());
+    }
+    
+    // Create the vault as a token account that is its own authority, so
+    // withdrawals are signed with the vault seeds
+    let rent = Rent::get()?;
+    invoke_signed(
+        &system_instruction::create_account(
+            authority.key,
+            treasury_vault.key,
+            rent.minimum_balance(TokenAccount::LEN),
+            TokenAccount::LEN as u64,
             token_program.key,
         ),
-        &[initializer.clone(), mint_account.clone()],
+        &[authority.clone(), treasury_vault.clone(), system_program_account.clone()],
+        &[&[TREASURY_VAULT_SEED, state_account.key.as_ref(), &[vault_bump]]],
+    )?;
+    
+    invoke(
+        &token_instruction::initialize_account3(
+            token_program.key,
+            treasury_vault.key,
+            mint_account.key,
+            treasury_vault.key,
+        )?,
+        &[treasury_vault.clone(), mint_account.clone(), token_program.clone()],
+    )?;
+    
+    state.treasury_vault = *treasury_vault.key;
+    state.serialize(&mut *state_account.data.borrow_mut())?;
+    
+    msg!("Initialized treasury vault {}", treasury_vault.key);
+    
+    Ok(())
+}
+
+// Withdraw tokens from the treasury vault
+fn process_withdraw_treasury(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let treasury_authority = next_account_info(account_info_iter)?;
+    let state_account = next_account_info(account_info_iter)?;
+    let treasury_vault = next_account_info(account_info_iter)?;
+    let destination = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let clock_account = next_account_info(account_info_iter)?;
+    
+    // Verify signer
+    if !treasury_authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    if state.config.treasury_authority == Pubkey::default() || state.config.treasury_authority != *treasury_authority.key {
+        return Err(SolDripError::Unauthorized.into());
+    }
+    if state.treasury_vault == Pubkey::default() {
+        return Err(SolDripError::TreasuryNotInitialized.into());
+    }
+    if *treasury_vault.key != state.treasury_vault {
+        return Err(SolDripError::InvalidTreasuryVault.into());
+    }
+    
+    // Count the withdrawal against the current period's limit
+    let clock = Clock::from_account_info(clock_account)?;
+    state.record_treasury_withdrawal(amount, clock.unix_timestamp)?;
+    
+    let (_, vault_bump) = find_treasury_vault_address(program_id, state_account.key);
+    invoke_signed(
+        &token_instruction::transfer(
+            token_program.key,
+            treasury_vault.key,
+            destination.key,
+            treasury_vault.key,
+            &[],
+            amount,
+        )?,
+        &[
+            treasury_vault.clone(),
+            destination.clone(),
+            treasury_vault.clone(),
+            token_program.clone(),
+        ],
+        &[&[TREASURY_VAULT_SEED, state_account.key.as_ref(), &[vault_bump]]],
     )?;
     
-    // Initialize mint
-    invoke(
-        &token_instruction::initialize_mint(
-            token_program.key,
-            mint_account.key,
-            initializer.key,
-            Some(initializer.key),
-            9, // Decimals: 9 to match SOL
-        )?,
-        &[
-            mint_account.clone(),
-            rent_account.clone(),
+    state.serialize(&mut *state_account.data.borrow_mut())?;
+    
+    // Withdrawal event for indexers
+    let event = TreasuryWithdrawal {
+        authority: *treasury_authority.key,
+        destination: *destination.key,
+        amount,
+        period_start: state.treasury_period_start,
+        withdrawn_in_period: state.treasury_withdrawn_in_period,
+        total_withdrawn: state.total_treasury_withdrawn,
+    };
+    sol_log_data(&[&event.try_to_vec()?]);
+    msg!("Withdrew {} treasury tokens to {}", amount, destination.key);
+    
+    Ok(())
+}
+
+// Quote a transfer's tax without moving tokens
+fn process_quote_transfer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    sender: &Pubkey,
+    destination: &Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let state_account = next_account_info(account_info_iter)?;
+    let clock_account = next_account_info(account_info_iter)?;
+    let source = next_account_info(account_info_iter)?;
+    let destination_account = next_account_info(account_info_iter)?;
+    let holder_record_account = next_account_info(account_info_iter)?;
+    let seller_record_account = next_account_info(account_info_iter)?;
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    let clock = Clock::from_account_info(clock_account)?;
+    
+    // Verify token accounts
+    if source.key != sender || destination_account.key != destination {
+        return Err(SolDripError::InvalidTokenAccount.into());
+    }
+    if !is_token_account(source, &spl_token::id()) || !is_token_account(destination_account, &spl_token::id()) {
+        return Err(SolDripError::InvalidTokenAccount.into());
+    }
+    let source_data = TokenAccount::unpack(&source.data.borrow())?;
+    let destination_data = TokenAccount::unpack(&destination_account.data.borrow())?;
+    
+    // Same tax engine and transfer rules as TransferWithTax
+    let (tier_index, tier) = state.transfer_tax_tier(amount, clock.slot);
+    let buyback_mode = state.buyback_mode();
+    let split = match buyback_mode {
+        true => tier.split(amount).into_buyback(),
+        false => tier.split(amount),
+    };
+    
+    let holder_record = load_holder_record(program_id, state_account.key, sender, holder_record_account)?;
+    let seller_record = match state.is_sell(destination) {
+        true => load_seller_record(program_id, state_account.key, &source_data.owner, seller_record_account)?,
+        false => None,
+    };
+    let violations = state.transfer_violations(
+        &TransferCheck {
+            source: *sender,
+            destination: *destination,
+            amount,
+            source_balance: source_data.amount,
+            destination_balance: destination_data.amount,
+            split,
+            exempt_from_limits: holder_record.as_ref().is_some_and(|record| record.exempt_from_limits),
+            last_sell_timestamp: seller_record.as_ref().map(|record| record.last_sell_timestamp),
+        },
+        clock.unix_timestamp,
+    );
+    
+    let quote = TransferQuote {
+        amount,
+        transfer_amount: split.transfer_amount,
+        lp_tax: split.lp,
+        dividend_tax: split.dividend,
+        burn_tax: split.burn,
+        treasury_tax: split.treasury,
+        tier_index: tier_index as u8,
+        tax_bps: tier.total_bps(),
+        buyback_mode,
+        violations,
+    };
+    
+    msg!("Quote: {} tokens delivered, {} bps tax, rejected: {}", quote.transfer_amount, quote.tax_bps, quote.rejected());
+    set_return_data(&quote.try_to_vec()?);
+    
+    Ok(())
+}
+
+// Report a token account's claimable dividends and rewards
+fn process_get_claimable(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let state_account = next_account_info(account_info_iter)?;
+    let clock_account = next_account_info(account_info_iter)?;
+    let token_holder = next_account_info(account_info_iter)?;
+    let holder_record_account = next_account_info(account_info_iter)?;
+    
+    // Deserialize state
+    if state_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    let clock = Clock::from_account_info(clock_account)?;
+    
+    // Verify token account
+    if !is_token_account(token_holder, &spl_token::id()) {
+        return Err(SolDripError::InvalidTokenAccount.into());
+    }
+    let token_data = TokenAccount::unpack(&token_holder.data.borrow())?;
+    if token_data.mint != state.mint {
+        return Err(SolDripError::InvalidTokenAccount.into());
+    }
+    
+    let holder_record = load_holder_record(program_id, state_account.key, token_holder.key, holder_record_account)?;
+    if holder_record.as_ref().is_some_and(|record| record.state != *state_account.key) {
+        return Err(SolDripError::InvalidHolderRecord.into());
+    }
+    
+    // Holders without a record earn the base multiplier and have no coin age
+    let coin_age = holder_record.as_ref().map(|record| record.coin_age(clock.unix_timestamp));
+    let milestone = coin_age.and_then(|coin_age| state.config.next_multiplier_milestone(coin_age));
+    
+    let mut claimable = ClaimableDividends {
+        token_account: *token_holder.key,
+        claimable_lamports: holder_record.as_ref().map_or(0, |record| record.accrued_lamports),
+        compound: holder_record.as_ref().is_some_and(|record| record.compound),
+        multiplier_bps: state.config.holding_multiplier_bps(coin_age),
+        next_multiplier_bps: milestone.map_or(0, |(_, multiplier_bps)| multiplier_bps),
+        next_milestone_timestamp: match (coin_age, milestone) {
+            (Some(coin_age), Some((milestone_age, _))) => clock.unix_timestamp + (milestone_age - coin_age),
+            _ => 0,
+        },
+        rewards: Vec::new(),
+    };
+    
+    // Same amounts ClaimRewards would pay: nothing until the position is opened
+    let stream_accounts: Vec<&AccountInfo> = account_info_iter.collect();
+    let stream_groups = stream_accounts.chunks_exact(3);
+    if !stream_groups.remainder().is_empty() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    
+    for stream_group in stream_groups {
+        let (stream_account, vault, position_account) = (stream_group[0], stream_group[1], stream_group[2]);
+        
+        let stream = load_reward_stream(program_id, state_account.key, stream_account)?;
+        if *vault.key != stream.vault {
+            return Err(SolDripError::InvalidRewardStream.into());
+        }
+        let (position_address, _) = find_reward_position_address(program_id, stream_account.key, token_holder.key);
+        if *position_account.key != position_address {
+            return Err(SolDripError::InvalidRewardStream.into());
+        }
+        
+        let amount = if position_account.data_is_empty() {
+            0
+        } else {
+            let position = RewardPosition::try_from_slice(&position_account.data.borrow())?;
+            let vault_balance = TokenAccount::unpack(&vault.data.borrow())?.amount;
+            position.pending(&stream, token_data.amount).min(vault_balance)
+        };
+        
+        claimable.rewards.push(ClaimableReward {
+            reward_mint: stream.reward_mint,
+            amount,
+        });
+    }
+    
+    msg!("Claimable: {} SOL, multiplier {} bps",
+         claimable.claimable_lamports as f64 / 1_000_000_000.0,
+         claimable.multiplier_bps);
+    set_return_data(&claimable.try_to_vec()?);
+    
+    Ok(())
+}
+
+// Swap adapter
+//
+// Compounding swaps SOL for DRIP through the external program configured in
+// `SolDripConfig::swap_program` (e.g. a thin wrapper around a Jupiter route).
+// The adapter program must accept:
+//   data:     [SWAP_ADAPTER_SWAP_SOL_FOR_TOKENS, amount_in: u64 LE, minimum_amount_out: u64 LE]
+//   accounts: 0. `[signer, writable]` Lamport source
+//             1. `[writable]` Destination token account
+//             2+ Venue-specific accounts, forwarded as passed to SolDrip
+// and deposit at least `minimum_amount_out` tokens into the destination.
+pub const SWAP_ADAPTER_SWAP_SOL_FOR_TOKENS: u8 = 0;
+
+pub const SWAP_ADAPTER_SWAP_TOKENS_FOR_TOKENS: u8 = 1;
+
+// Token-for-token swaps use the same layout, with accounts:
+//   0. `[signer]` Source token account authority
+//   1. `[writable]` Source token account
+//   2. `[writable]` Destination token account
+//   3+ Venue-specific accounts
+
+// Swap lamports held by a PDA into tokens, returning the amount received
+fn swap_sol_for_tokens<'a>(
+    swap_program: &AccountInfo<'a>,
+    source: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    swap_accounts: &[AccountInfo<'a>],
+    amount_in: u64,
+    minimum_amount_out: u64,
+    source_seeds: &[&[u8]],
+) -> Result<u64, ProgramError> {
+    invoke_swap_adapter(
+        swap_program,
+        SWAP_ADAPTER_SWAP_SOL_FOR_TOKENS,
+        vec![
+            AccountMeta::new(*source.key, true),
+            AccountMeta::new(*destination.key, false),
         ],
-    )?;
+        vec![source.clone(), destination.clone()],
+        source,
+        |account| Ok(account.lamports()),
+        destination,
+        swap_accounts,
+        amount_in,
+        minimum_amount_out,
+        source_seeds,
+    )
+}
+
+// Swap tokens held by a PDA-owned token account, returning the amount received
+#[allow(clippy::too_many_arguments)]
+fn swap_tokens_for_tokens<'a>(
+    swap_program: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    source: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    swap_accounts: &[AccountInfo<'a>],
+    amount_in: u64,
+    minimum_amount_out: u64,
+    authority_seeds: &[&[u8]],
+) -> Result<u64, ProgramError> {
+    invoke_swap_adapter(
+        swap_program,
+        SWAP_ADAPTER_SWAP_TOKENS_FOR_TOKENS,
+        vec![
+            AccountMeta::new_readonly(*authority.key, true),
+            AccountMeta::new(*source.key, false),
+            AccountMeta::new(*destination.key, false),
+        ],
+        vec![authority.clone(), source.clone(), destination.clone()],
+        source,
+        |account| Ok(TokenAccount::unpack(&account.data.borrow())?.amount),
+        destination,
+        swap_accounts,
+        amount_in,
+        minimum_amount_out,
+        authority_seeds,
+    )
+}
+
+// Invoke the swap adapter. The amounts spent and received are measured
+// from the source and destination balances rather than trusted from the
+// adapter, which runs with the source's signer and caller-chosen accounts.
+#[allow(clippy::too_many_arguments)]
+fn invoke_swap_adapter<'a>(
+    swap_program: &AccountInfo<'a>,
+    instruction_tag: u8,
+    mut account_metas: Vec<AccountMeta>,
+    mut account_infos: Vec<AccountInfo<'a>>,
+    source: &AccountInfo<'a>,
+    source_balance: fn(&AccountInfo) -> Result<u64, ProgramError>,
+    destination: &AccountInfo<'a>,
+    swap_accounts: &[AccountInfo<'a>],
+    amount_in: u64,
+    minimum_amount_out: u64,
+    signer_seeds: &[&[u8]],
+) -> Result<u64, ProgramError> {
+    let source_before = source_balance(source)?;
+    let balance_before = TokenAccount::unpack(&destination.data.borrow())?.amount;
     
-    // Initialize state account
-    let state_space = std::mem::size_of::<SolDripState>();
-    let state_rent = rent.minimum_balance(state_space);
+    let mut data = Vec::with_capacity(17);
+    data.push(instruction_tag);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&minimum_amount_out.to_le_bytes());
     
-    invoke(
-        &system_instruction::create_account(
-            initializer.key,
-            state_account.key,
-            state_rent,
-            state_space as u64,
-            program_id,
-        ),
-        &[initializer.clone(), state_account.clone()],
+    account_metas.extend(swap_accounts.iter().map(|account| AccountMeta {
+        pubkey: *account.key,
+        is_signer: account.is_signer,
+        is_writable: account.is_writable,
+    }));
+    account_infos.extend_from_slice(swap_accounts);
+    account_infos.push(swap_program.clone());
+    
+    invoke_signed(
+        &Instruction {
+            program_id: *swap_program.key,
+            accounts: account_metas,
+            data,
+        },
+        &account_infos,
+        &[signer_seeds],
     )?;
     
-    // Initialize dividend pool
-    invoke(
+    if source_before.saturating_sub(source_balance(source)?) > amount_in {
+        return Err(SolDripError::SwapInputTooHigh.into());
+    }
+    
+    let balance_after = TokenAccount::unpack(&destination.data.borrow())?.amount;
+    let received = balance_after.saturating_sub(balance_before);
+    if received < minimum_amount_out {
+        return Err(SolDripError::SwapOutputTooLow.into());
+    }
+    
+    Ok(received)
+}
+
+// Helper function to load a holder record on behalf of the token account
+// owner, verifying the signer owns the token account
+fn load_owned_holder_record(
+    program_id: &Pubkey,
+    owner: &AccountInfo,
+    token_holder: &AccountInfo,
+    holder_record_account: &AccountInfo,
+) -> Result<(TokenAccount, HolderRecord), ProgramError> {
+    if !is_token_account(token_holder, &spl_token::id()) {
+        return Err(SolDripError::InvalidTokenAccount.into());
+    }
+    let token_data = TokenAccount::unpack(&token_holder.data.borrow())?;
+    if token_data.owner != *owner.key {
+        return Err(SolDripError::NotTokenAccountOwner.into());
+    }
+    
+    if holder_record_account.owner != program_id {
+        return Err(SolDripError::InvalidHolderRecord.into());
+    }
+    let holder_record = HolderRecord::try_from_slice(&holder_record_account.data.borrow())?;
+    if holder_record.token_account != *token_holder.key {
+        return Err(SolDripError::InvalidHolderRecord.into());
+    }
+    
+    Ok((token_data, holder_record))
+}
+
+// Helper function to load a reward stream belonging to a state account
+fn load_reward_stream(
+    program_id: &Pubkey,
+    state_account: &Pubkey,
+    stream_account: &AccountInfo,
+) -> Result<RewardStream, ProgramError> {
+    if stream_account.owner != program_id {
+        return Err(SolDripError::InvalidRewardStream.into());
+    }
+    let stream = RewardStream::try_from_slice(&stream_account.data.borrow())?;
+    if stream.state != *state_account {
+        return Err(SolDripError::InvalidRewardStream.into());
+    }
+    
+    Ok(stream)
+}
+
+// Helper function to load every reward stream of a state, passed once each in
+// ascending address order
+fn load_reward_streams(
+    program_id: &Pubkey,
+    state: &SolDripState,
+    state_account: &Pubkey,
+    stream_accounts: &[&AccountInfo],
+) -> Result<Vec<RewardStream>, ProgramError> {
+    if stream_accounts.len() != state.reward_stream_count as usize
+        || stream_accounts.windows(2).any(|pair| pair[0].key >= pair[1].key)
+    {
+        return Err(SolDripError::MissingRewardStreams.into());
+    }
+    
+    stream_accounts
+        .iter()
+        .map(|stream_account| load_reward_stream(program_id, state_account, stream_account))
+        .collect()
+}
+
+// Helper function to load an optional holder record, verifying its address.
+// Returns `None` if the record has not been created yet.
+fn load_holder_record(
+    program_id: &Pubkey,
+    state_account: &Pubkey,
+    token_account: &Pubkey,
+    holder_record_account: &AccountInfo,
+) -> Result<Option<HolderRecord>, ProgramError> {
+    let (holder_record_address, _) = find_holder_record_address(program_id, state_account, token_account);
+    if *holder_record_account.key != holder_record_address {
+        return Err(SolDripError::InvalidHolderRecord.into());
+    }
+    
+    if holder_record_account.data_is_empty() {
+        return Ok(None);
+    }
+    if holder_record_account.owner != program_id {
+        return Err(SolDripError::InvalidHolderRecord.into());
+    }
+    
+    Ok(Some(HolderRecord::try_from_slice(&holder_record_account.data.borrow())?))
+}
+
+// Helper function to settle a token account's reward position around a
+// balance change, verifying its address. Accounts without a position are skipped.
+fn settle_reward_position(
+    program_id: &Pubkey,
+    stream: &mut RewardStream,
+    stream_address: &Pubkey,
+    token_account: &Pubkey,
+    position_account: &AccountInfo,
+    balance_before: u64,
+    balance_after: u64,
+) -> ProgramResult {
+    let (position_address, _) = find_reward_position_address(program_id, stream_address, token_account);
+    if *position_account.key != position_address {
+        return Err(SolDripError::InvalidRewardStream.into());
+    }
+    
+    if position_account.data_is_empty() {
+        return Ok(());
+    }
+    if position_account.owner != program_id {
+        return Err(SolDripError::InvalidRewardStream.into());
+    }
+    
+    let mut position = RewardPosition::try_from_slice(&position_account.data.borrow())?;
+    position.settle(stream, balance_before, balance_after);
+    position.serialize(&mut *position_account.data.borrow_mut())?;
+    
+    Ok(())
+}
+
+// Helper function to load a wallet's optional seller record, verifying its
+// address. Returns `None` if the wallet has not sold yet.
+fn load_seller_record(
+    program_id: &Pubkey,
+    state_account: &Pubkey,
+    owner: &Pubkey,
+    seller_record_account: &AccountInfo,
+) -> Result<Option<SellerRecord>, ProgramError> {
+    let (seller_record_address, _) = find_seller_record_address(program_id, state_account, owner);
+    if *seller_record_account.key != seller_record_address {
+        return Err(SolDripError::InvalidSellerRecord.into());
+    }
+    
+    if seller_record_account.data_is_empty() {
+        return Ok(None);
+    }
+    if seller_record_account.owner != program_id {
+        return Err(SolDripError::InvalidSellerRecord.into());
+    }
+    
+    Ok(Some(SellerRecord::try_from_slice(&seller_record_account.data.borrow())?))
+}
+
+// Create the seller record PDA of a wallet, paid for by `payer`
+fn create_seller_record<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    state_account: &AccountInfo<'a>,
+    owner: &Pubkey,
+    seller_record_account: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+) -> Result<SellerRecord, ProgramError> {
+    let (_, bump) = find_seller_record_address(program_id, state_account.key, owner);
+    let rent = Rent::get()?;
+    invoke_signed(
         &system_instruction::create_account(
-            initializer.key,
-            dividend_pool.key,
-            rent.minimum_balance(0),
-            0,
+            payer.key,
+            seller_record_account.key,
+            rent.minimum_balance(SellerRecord::LEN),
+            SellerRecord::LEN as u64,
             program_id,
         ),
-        &[initializer.clone(), dividend_pool.clone()],
+        &[payer.clone(), seller_record_account.clone(), system_program_account.clone()],
+        &[&[SELLER_RECORD_SEED, state_account.key.as_ref(), owner.as_ref(), &[bump]]],
     )?;
     
-    // Initialize LP pool
-    invoke(
+    Ok(SellerRecord {
+        state: *state_account.key,
+        owner: *owner,
+        last_sell_timestamp: 0,
+        bump,
+    })
+}
+
+// Create the holder record PDA of a token account, paid for by `payer`.
+// Nothing is counted in the total weight until the caller weighs the record.
+fn create_holder_record<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    state_account: &AccountInfo<'a>,
+    token_holder: &AccountInfo<'a>,
+    holder_record_account: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+    distribution_epoch: u64,
+) -> Result<HolderRecord, ProgramError> {
+    let (holder_record_address, bump) = find_holder_record_address(program_id, state_account.key, token_holder.key);
+    if *holder_record_account.key != holder_record_address || !holder_record_account.data_is_empty() {
+        return Err(SolDripError::InvalidHolderRecord.into());
+    }
+    
+    let rent = Rent::get()?;
+    invoke_signed(
         &system_instruction::create_account(
-            initializer.key,
-            lp_pool.key,
-            rent.minimum_balance(0),
-            0,
+            payer.key,
+            holder_record_account.key,
+            rent.minimum_balance(HolderRecord::LEN),
+            HolderRecord::LEN as u64,
             program_id,
         ),
-        &[initializer.clone(), lp_pool.clone()],
+        &[payer.clone(), holder_record_account.clone(), system_program_account.clone()],
+        &[&[HOLDER_RECORD_SEED, state_account.key.as_ref(), token_holder.key.as_ref(), &[bump]]],
     )?;
     
-    // Initialize state data
-    let state = SolDripState {
-        mint: *mint_account.key,
-        dividend_pool: *dividend_pool.key,
-        lp_pool: *lp_pool.key,
-        total_sol_distributed: 0,
-        last_distribution_timestamp: 0,
-        price_fluctuation_bps: 0,
-        slippage_protection_active: false,
-        slippage_protection_timestamp: 0,
-        total_supply,
-    };
+    Ok(HolderRecord {
+        state: *state_account.key,
+        token_account: *token_holder.key,
+        payout_address: Pubkey::default(),
+        bump,
+        compound: false,
+        accrued_lamports: 0,
+        exempt_from_limits: false,
+        holding_since: Clock::get()?.unix_timestamp,
+        // A new record holds nothing for the current epoch, so it first
+        // earns for the next full accrual period
+        balance_epoch: distribution_epoch,
+        epoch_min_balance: 0,
+        previous_epoch_min_balance: 0,
+        weight_balance: 0,
+        weight_multiplier_bps: 0,
+        paid_round: 0,
+        weight_epoch: distribution_epoch,
+        epoch_start_weight: 0,
+    })
+}
+
+// Helper function to check if an account is a token account
+fn is_token_account(account: &AccountInfo, token_program_id: &Pubkey) -> bool {
+    // Check if account is owned by the token program
+    account.owner == token_program_id
+}
+
+// Helper function to take `bps` basis points of an amount, rounding down.
+// Computed in u128 since raw token amounts times 10_000 can overflow u64.
+fn bps_of(amount: u64, bps: u16) -> u64 {
+    (amount as u128 * bps as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+// Helper function to calculate a holder's dividend weight; also used by
+// off-chain snapshot builders
+pub fn dividend_weight(token_balance: u64, multiplier_bps: u64) -> u128 {
+    token_balance as u128 * multiplier_bps as u128
+}
+
+// Helper function to calculate dividend share. Shares are rounded down, so
+// as long as the weights paid sum to at most `total_weight`, the payouts
+// sum to at most `distributable_lamports`.
+pub fn calculate_dividend_share(
+    weight: u128,
+    total_weight: u128,
+    distributable_lamports: u64,
+) -> u64 {
+    if weight == 0 || total_weight == 0 {
+        return 0;
+    }
     
-    state.serialize(&mut *state_account.data.borrow_mut())?;
+    (weight.min(total_weight) * distributable_lamports as u128 / total_weight) as u64
+}
+
+// Helper function to check if price fluctuation is above threshold
+fn is_price_fluctuation_above_threshold(current_price: u64, previous_price: u64) -> bool {
+    if previous_price == 0 {
+        return false;
+    }
     
-    msg!("SolDrip token initialized with total supply: {}", total_supply);
+    // Calculate fluctuation in basis points (1/100 of a percent)
+    let fluctuation = if current_price > previous_price {
+        (current_price - previous_price) * 10000 / previous_price
+    } else {
+        (previous_price - current_price) * 10000 / previous_price
+    };
     
-    Ok(())
+    fluctuation as u16 > PRICE_FLUCTUATION_THRESHOLD_BPS
 }
 
-// Transfer tokens with tax
-fn process_transfer_with_tax(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    amount: u64,
-) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
+#[cfg(test)]
+mod tests {
+    use super::*;
     
-    // Get accounts
-    let sender = next_account_info(account_info_iter)?;
-    let source = next_account_info(account_info_iter)?;
-    let destination = next_account_info(account_info_iter)?;
-    let dividend_pool = next_account_info(account_info_iter)?;
-    let lp_pool = next_account_info(account_info_iter)?;
-    let state_account = next_account_info(account_info_iter)?;
-    let token_program = next_account_info(account_info_iter)?;
-    let clock_account = next_account_info(account_info_iter)?;
+    const AMOUNTS: [u64; 9] = [0, 1, 19, 99, 10_001, 123_456_789, 999_999_999_999, 1_000_000_000_000_000_000, u64::MAX];
+    
+    fn test_state() -> SolDripState {
+        let mut state = SolDripState::try_from_slice(&[0u8; SolDripState::LEN]).unwrap();
+        state.config = SolDripConfig::default();
+        state
+    }
+    
+    fn holder_record() -> HolderRecord {
+        HolderRecord {
+            state: Pubkey::default(),
+            token_account: Pubkey::default(),
+            payout_address: Pubkey::default(),
+            bump: 0,
+            compound: false,
+            accrued_lamports: 0,
+            exempt_from_limits: false,
+            holding_since: 0,
+            balance_epoch: 0,
+            epoch_min_balance: 0,
+            previous_epoch_min_balance: 0,
+            weight_balance: 0,
+            weight_multiplier_bps: 0,
+            paid_round: 0,
+            weight_epoch: 0,
+            epoch_start_weight: 0,
+        }
+    }
+    
+    fn distribution_history() -> DistributionHistory {
+        DistributionHistory {
+            state: Pubkey::default(),
+            total_rounds: 0,
+            entries: [RoundSummary::default(); DISTRIBUTION_HISTORY_CAPACITY],
+            bump: 0,
+        }
+    }
+    
+    fn reward_stream() -> RewardStream {
+        RewardStream {
+            state: Pubkey::default(),
+            reward_mint: Pubkey::default(),
+            vault: Pubkey::default(),
+            tax_account: Pubkey::default(),
+            tax_share_bps: 0,
+            reward_per_token: 0,
+            total_funded: 0,
+            total_claimed: 0,
+            bump: 0,
+            total_position_balance: 0,
+            unallocated: 0,
+        }
+    }
+    
+    fn reward_position(balance: u64) -> RewardPosition {
+        RewardPosition {
+            stream: Pubkey::default(),
+            token_account: Pubkey::default(),
+            reward_per_token_paid: 0,
+            balance,
+            accrued: 0,
+            total_claimed: 0,
+            bump: 0,
+        }
+    }
+    
+    fn merkle_distribution() -> MerkleDistribution {
+        MerkleDistribution {
+            state: Pubkey::new_unique(),
+            distribution_id: 1,
+            epoch: 1,
+            merkle_root: [0; 32],
+            total_amount: 0,
+            claimed_amount: 0,
+            claim_count: 0,
+            claims_made: 0,
+            published_at: 0,
+            publisher: Pubkey::default(),
+            bump: 0,
+            expires_at: MERKLE_CLAIM_PERIOD,
+            reclaimed_amount: 0,
+            reclaimed: false,
+        }
+    }
+    
+    fn assert_exact(split: &TaxSplit, amount: u64) {
+        assert_eq!(split.transfer_amount + split.total_tax(), amount, "{:?} does not add up to {}", split, amount);
+    }
+    
+    #[test]
+    fn split_is_exact_for_every_default_tier() {
+        let config = SolDripConfig::default();
+        for tier in &config.tax_tiers[..config.tax_tier_count as usize] {
+            for amount in AMOUNTS {
+                let split = tier.split(amount);
+                assert_exact(&split, amount);
+                assert_eq!(split.total_tax(), bps_of(amount, tier.total_bps()));
+                
+                // Buckets never exceed their exact share, and only the
+                // dividend bucket picks up dust
+                assert!(split.lp <= bps_of(amount, tier.lp_bps));
+                assert!(split.burn <= bps_of(amount, tier.burn_bps));
+                assert!(split.treasury <= bps_of(amount, tier.treasury_bps));
+                assert!(split.dividend >= bps_of(amount, tier.dividend_bps));
+            }
+        }
+    }
+    
+    #[test]
+    fn split_assigns_dust_to_dividends() {
+        let tier = TaxTier { min_amount_bps: 0, lp_bps: 100, dividend_bps: 500, burn_bps: 100, treasury_bps: 0 };
+        // 7% of 99 rounds down to 6; 1/7 of 6 rounds down to 0 for LP and burn
+        let split = tier.split(99);
+        assert_eq!(split, TaxSplit { transfer_amount: 93, lp: 0, dividend: 6, burn: 0, treasury: 0 });
+    }
+    
+    #[test]
+    fn split_handles_fractional_and_empty_tiers() {
+        let tier = TaxTier { min_amount_bps: 0, lp_bps: 50, dividend_bps: 400, burn_bps: 0, treasury_bps: 0 };
+        let split = tier.split(1_000_000);
+        assert_eq!(split, TaxSplit { transfer_amount: 955_000, lp: 5_000, dividend: 40_000, burn: 0, treasury: 0 });
+        
+        let split = TaxTier::default().split(1_000_000);
+        assert_eq!(split, TaxSplit { transfer_amount: 1_000_000, ..TaxSplit::default() });
+    }
+    
+    #[test]
+    fn split_is_exact_under_launch_tax() {
+        let config = SolDripConfig::default();
+        for slot in [0, 1, 75, 149, 150] {
+            if let Some(launch_tax_bps) = config.launch_tax_bps(0, slot) {
+                assert!((TAX_BPS..=DEFAULT_LAUNCH_TAX_BPS).contains(&launch_tax_bps));
+            }
+            for tier in &config.tax_tiers[..config.tax_tier_count as usize] {
+                let mut tier = *tier;
+                if let Some(launch_tax_bps) = config.launch_tax_bps(0, slot) {
+                    if launch_tax_bps > tier.total_bps() {
+                        tier.dividend_bps = launch_tax_bps - tier.lp_bps - tier.burn_bps - tier.treasury_bps;
+                    }
+                }
+                for amount in AMOUNTS {
+                    assert_exact(&tier.split(amount), amount);
+                }
+            }
+        }
+    }
+    
+    #[test]
+    fn buyback_deducts_the_whole_tax() {
+        let config = SolDripConfig::default();
+        for tier in &config.tax_tiers[..config.tax_tier_count as usize] {
+            for amount in AMOUNTS {
+                let split = tier.split(amount);
+                let buyback = split.into_buyback();
+                assert_exact(&buyback, amount);
+                assert_eq!(buyback.total_tax(), split.total_tax());
+                assert_eq!(buyback.lp, bps_of(split.total_tax(), SLIPPAGE_BUYBACK_BPS));
+            }
+        }
+    }
+    
+    #[test]
+    fn weighted_shares_never_exceed_the_pool() {
+        let distributable = 1_000_000_007;
+        let balances = [333_333_333u64, 333_333_333, 333_333_334];
+        let total_weight = dividend_weight(balances.iter().sum(), HOLDING_BONUS_MULTIPLIER_BPS);
+        
+        // Everyone holding the bonus pays out the whole pool, minus rounding
+        let paid: u64 = balances
+            .iter()
+            .map(|&balance| calculate_dividend_share(dividend_weight(balance, HOLDING_BONUS_MULTIPLIER_BPS), total_weight, distributable))
+            .sum();
+        assert!(paid <= distributable && distributable - paid < balances.len() as u64);
+        
+        // Without the bonus the unpaid weight stays in the pool
+        let paid: u64 = balances
+            .iter()
+            .map(|&balance| calculate_dividend_share(dividend_weight(balance, BASE_MULTIPLIER_BPS), total_weight, distributable))
+            .sum();
+        assert!(paid <= distributable * 10 / 11);
+    }
+    
+    #[test]
+    fn tracked_weight_pays_out_the_whole_pool() {
+        let day = SECONDS_PER_DAY;
+        let now = 100 * day;
+        let distributable = 1_000_000_007;
+        
+        for curve in [CoinAgeCurve::StepBonus, CoinAgeCurve::Linear] {
+            let mut state = test_state();
+            state.config.coin_age_curve = curve;
+            state.config.coin_age_max_multiplier_bps = 30_000;
+            state.config.snapshot_eligibility = false;
+            
+            // One long-time holder, one mid-ramp and one fresh buyer
+            let mut records: Vec<(HolderRecord, u64)> = [(now - 60 * day, 400_000u64), (now - 15 * day, 350_000), (now, 250_000)]
+                .into_iter()
+                .map(|(holding_since, balance)| {
+                    let mut record = HolderRecord { holding_since, ..holder_record() };
+                    record.track_balance(&mut state, 0, balance, now);
+                    (record, balance)
+                })
+                .collect();
+            assert_eq!(state.total_weight, records.iter().map(|(record, _)| record.counted_weight()).sum::<u128>());
+            
+            let paid: u64 = records
+                .iter()
+                .map(|(record, balance)| {
+                    let (_, weight) = record.payout_weight(&state.config, 1, *balance);
+                    calculate_dividend_share(weight, state.total_weight, distributable)
+                })
+                .sum();
+            assert!(paid <= distributable && distributable - paid < records.len() as u64, "{:?}: paid {}", curve, paid);
+            
+            // A sell lowers both the record's weight and the total
+            let (record, balance) = &mut records[0];
+            record.track_balance(&mut state, *balance, *balance / 2, now);
+            *balance /= 2;
+            assert_eq!(state.total_weight, records.iter().map(|(record, _)| record.counted_weight()).sum::<u128>());
+        }
+    }
     
-    // Verify signer
-    if !sender.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
+    #[test]
+    fn closed_token_accounts_are_passed_while_they_count() {
+        let owner = Pubkey::new_unique();
+        let mut record = HolderRecord { token_account: Pubkey::new_unique(), weight_balance: 1_000, ..holder_record() };
+        
+        // A closed or emptied account still counting weight is passed to drop it
+        assert_eq!(record.distribution_recipient(None), Some(record.token_account));
+        assert_eq!(record.distribution_recipient(Some((owner, 0))), Some(owner));
+        
+        // Once it counts nothing, only a funded account is worth passing
+        record.weight_balance = 0;
+        assert_eq!(record.distribution_recipient(None), None);
+        assert_eq!(record.distribution_recipient(Some((owner, 0))), None);
+        assert_eq!(record.distribution_recipient(Some((owner, 1))), Some(owner));
     }
     
-    // Deserialize state
-    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    #[test]
+    fn pools_and_vaults_earn_no_dividends() {
+        let mut state = test_state();
+        let holder = Pubkey::new_unique();
+        // Unset accounts exclude nothing
+        assert_eq!(state.dividend_exclusions().count(), 0);
+        assert!(state.earns_dividends(&Pubkey::default()));
+        
+        state.config.market_token_account = Pubkey::new_unique();
+        state.lp_pool = Pubkey::new_unique();
+        state.treasury_vault = Pubkey::new_unique();
+        state.dividend_pool = Pubkey::new_unique();
+        for excluded in [state.config.market_token_account, state.lp_pool, state.treasury_vault, state.dividend_tax_account()] {
+            assert!(!state.earns_dividends(&excluded));
+        }
+        assert!(state.earns_dividends(&holder));
+    }
     
-    // Get clock
-    let clock = Clock::from_account_info(clock_account)?;
+    #[test]
+    fn payout_weight_never_exceeds_the_counted_weight() {
+        let mut state = test_state();
+        state.config.snapshot_eligibility = false;
+        let mut record = holder_record();
+        record.track_balance(&mut state, 0, 1_000, 0);
+        
+        // Tokens received outside TransferWithTax are not counted yet
+        let (balance, weight) = record.payout_weight(&state.config, 1, 5_000);
+        assert_eq!(balance, 1_000);
+        assert_eq!(weight, record.counted_weight());
+        
+        // Tokens sent away outside TransferWithTax stop earning at once
+        let (balance, _) = record.payout_weight(&state.config, 1, 300);
+        assert_eq!(balance, 300);
+    }
     
-    // Verify token accounts
-    if !is_token_account(source, token_program.key) || !is_token_account(destination, token_program.key) {
-        return Err(SolDripError::InvalidTokenAccount.into());
+    #[test]
+    fn rounds_pay_the_weight_counted_at_their_start() {
+        let mut state = test_state();
+        state.config.snapshot_eligibility = false;
+        let (mut buyer, mut holder) = (holder_record(), holder_record());
+        buyer.track_balance(&mut state, 0, 1_000, 0);
+        holder.track_balance(&mut state, 0, 1_000, 0);
+        
+        // A round opens epoch 1 and snapshots the total weight
+        state.distribution_epoch = 1;
+        let round_weight = state.total_weight;
+        
+        // Buying during the round counts from the next round on, so the
+        // holder paid after the buyer is not squeezed out
+        buyer.track_balance(&mut state, 1_000, 3_000, 0);
+        let (_, buyer_weight) = buyer.payout_weight(&state.config, 1, 3_000);
+        let (_, holder_weight) = holder.payout_weight(&state.config, 1, 1_000);
+        assert_eq!(buyer_weight, holder_weight);
+        assert_eq!(buyer_weight + holder_weight, round_weight);
+        
+        assert_eq!(buyer.payout_weight(&state.config, 2, 3_000).1, buyer.counted_weight());
+        assert_eq!(state.total_weight, buyer.counted_weight() + holder.counted_weight());
     }
     
-    // Load token accounts data
-    let source_data = TokenAccount::unpack(&source.data.borrow())?;
-    let destination_data = TokenAccount::unpack(&destination.data.borrow())?;
+    #[test]
+    fn skipping_ahead_leaves_lower_holders_payable() {
+        let round_id = 7;
+        let mut records: Vec<HolderRecord> = (0..3).map(|_| HolderRecord { paid_round: round_id - 1, ..holder_record() }).collect();
+        
+        // A batch that jumps straight to the highest holder
+        assert_eq!(records[2].mark_paid(round_id), Ok(()));
+        
+        // Lower holders are still paid, in any order, exactly once
+        assert_eq!(records[0].mark_paid(round_id), Ok(()));
+        assert_eq!(records[1].mark_paid(round_id), Ok(()));
+        for record in &mut records {
+            assert_eq!(record.mark_paid(round_id), Err(SolDripError::HolderAlreadyPaid));
+        }
+        
+        // The next round pays everyone again
+        assert!(records.iter_mut().all(|record| record.mark_paid(round_id + 1).is_ok()));
+    }
     
-    // Check if source has enough tokens
-    if source_data.amount < amount {
-        return Err(SolDripError::InsufficientTokenBalance.into());
+    #[test]
+    fn coin_age_curves() {
+        let day = SECONDS_PER_DAY;
+        let mut config = SolDripConfig::default();
+        assert_eq!(config.holding_multiplier_bps(None), BASE_MULTIPLIER_BPS);
+        assert_eq!(config.holding_multiplier_bps(Some(6 * day)), BASE_MULTIPLIER_BPS);
+        assert_eq!(config.holding_multiplier_bps(Some(7 * day)), HOLDING_BONUS_MULTIPLIER_BPS);
+        
+        config.coin_age_curve = CoinAgeCurve::Linear;
+        assert!(config.validate().is_ok());
+        assert_eq!(config.holding_multiplier_bps(Some(0)), BASE_MULTIPLIER_BPS);
+        assert_eq!(config.holding_multiplier_bps(Some(15 * day)), 12_500);
+        assert_eq!(config.holding_multiplier_bps(Some(300 * day)), 15_000);
+        assert_eq!(config.max_holding_multiplier_bps(), 15_000);
+        
+        config.coin_age_curve = CoinAgeCurve::Tiered;
+        assert!(config.validate().is_err());
+        config.coin_age_tiers[0] = CoinAgeTier { min_age: day, multiplier_bps: 10_500 };
+        config.coin_age_tiers[1] = CoinAgeTier { min_age: 30 * day, multiplier_bps: 12_000 };
+        config.coin_age_tier_count = 2;
+        assert!(config.validate().is_ok());
+        assert_eq!(config.holding_multiplier_bps(Some(day - 1)), BASE_MULTIPLIER_BPS);
+        assert_eq!(config.holding_multiplier_bps(Some(10 * day)), 10_500);
+        assert_eq!(config.holding_multiplier_bps(Some(31 * day)), 12_000);
+        assert_eq!(config.max_holding_multiplier_bps(), 12_000);
     }
     
-    // Check if transaction is a large sale (> 0.2% of total supply)
-    let large_sale_threshold = state.total_supply * LARGE_SALE_THRESHOLD_PERCENTAGE as u64 / 100;
-    let is_large_sale = amount > large_sale_threshold;
+    #[test]
+    fn next_multiplier_milestones() {
+        let day = SECONDS_PER_DAY;
+        let mut config = SolDripConfig::default();
+        assert_eq!(config.next_multiplier_milestone(day), Some((7 * day, HOLDING_BONUS_MULTIPLIER_BPS)));
+        assert_eq!(config.next_multiplier_milestone(7 * day), None);
+        
+        config.coin_age_curve = CoinAgeCurve::Linear;
+        assert_eq!(config.next_multiplier_milestone(day), Some((30 * day, 15_000)));
+        assert_eq!(config.next_multiplier_milestone(30 * day), None);
+        
+        config.coin_age_curve = CoinAgeCurve::Tiered;
+        config.coin_age_tiers[0] = CoinAgeTier { min_age: day, multiplier_bps: 10_500 };
+        config.coin_age_tiers[1] = CoinAgeTier { min_age: 30 * day, multiplier_bps: 12_000 };
+        config.coin_age_tier_count = 2;
+        assert_eq!(config.next_multiplier_milestone(0), Some((day, 10_500)));
+        assert_eq!(config.next_multiplier_milestone(10 * day), Some((30 * day, 12_000)));
+        assert_eq!(config.next_multiplier_milestone(30 * day), None);
+    }
     
-    // Calculate tax amounts
-    let (dividend_tax_percentage, total_tax_percentage) = if is_large_sale {
-        (HIGH_DIVIDEND_TAX_PERCENTAGE, HIGH_TAX_PERCENTAGE)
-    } else {
-        (DIVIDEND_TAX_PERCENTAGE, TAX_PERCENTAGE)
-    };
+    #[test]
+    fn coin_age_resets_in_proportion_to_sells() {
+        let now = 1_000_000;
+        let mut record = HolderRecord { holding_since: now - 1_000, ..holder_record() };
+        
+        record.reset_coin_age_on_sell(100, 25, now);
+        assert_eq!(record.coin_age(now), 750);
+        record.dilute_coin_age_on_receive(75, 75, now);
+        assert_eq!(record.coin_age(now), 375);
+        record.reset_coin_age_on_sell(150, 150, now);
+        assert_eq!(record.coin_age(now), 0);
+    }
     
-    let total_tax = amount * total_tax_percentage as u64 / 100;
-    let lp_tax = amount * LP_TAX_PERCENTAGE as u64 / 100;
-    let dividend_tax = amount * dividend_tax_percentage as u64 / 100;
-    let transfer_amount = amount - total_tax;
+    #[test]
+    fn only_balances_held_across_the_epoch_are_eligible() {
+        let mut state = test_state();
+        let mut record = HolderRecord { balance_epoch: 1, ..holder_record() };
+        let track = |record: &mut HolderRecord, state: &mut SolDripState, epoch, before, after| {
+            state.distribution_epoch = epoch;
+            record.track_balance(state, before, after, 0);
+        };
+        
+        // Created during epoch 1 and bought in: nothing held across epoch 1
+        track(&mut record, &mut state, 1, 0, 1_000);
+        assert_eq!(record.eligible_balance(2, 1_000), 0);
+        
+        // Held through epoch 2, then a flash buy and sell during epoch 3
+        track(&mut record, &mut state, 3, 1_000, 5_000);
+        track(&mut record, &mut state, 3, 5_000, 1_000);
+        assert_eq!(record.eligible_balance(3, 1_000), 1_000);
+        assert_eq!(record.eligible_balance(4, 1_000), 1_000);
+        
+        // A partial sell during epoch 4 lowers what epoch 4 earns
+        track(&mut record, &mut state, 4, 1_000, 400);
+        assert_eq!(record.eligible_balance(5, 400), 400);
+        
+        // Untouched for several epochs: the live balance was held throughout
+        assert_eq!(record.eligible_balance(9, 400), 400);
+        
+        // A transfer right after round 9 starts keeps epoch 8's snapshot
+        track(&mut record, &mut state, 9, 400, 900);
+        assert_eq!(record.eligible_balance(9, 900), 400);
+    }
     
-    // Check if destination would exceed maximum holding limit
-    let max_holding = state.total_supply * MAX_HOLDING_PERCENTAGE as u64 / 100;
-    if destination_data.amount + transfer_amount > max_holding {
-        return Err(SolDripError::ExceedsMaximumHolding.into());
+    #[test]
+    fn unseen_buys_earn_from_the_round_after_they_are_observed() {
+        let mut state = test_state();
+        assert!(state.config.snapshot_eligibility);
+        let mut record = holder_record();
+        record.track_balance(&mut state, 0, 100, 0);
+        
+        // Round 1 observes the balance when it pays the record
+        state.distribution_epoch = 1;
+        record.track_balance(&mut state, 100, 100, 0);
+        
+        // Tokens bought on the DEX during epoch 1 are not paid by round 2
+        state.distribution_epoch = 2;
+        assert_eq!(record.payout_weight(&state.config, 2, 10_100).0, 100);
+        record.track_balance(&mut state, 10_100, 10_100, 0);
+        
+        // Round 2 observed them, so round 3 pays them once they were held throughout
+        state.distribution_epoch = 3;
+        assert_eq!(record.payout_weight(&state.config, 3, 10_100).0, 10_100);
     }
     
-    // Check if slippage protection is active
-    if state.slippage_protection_active {
-        let protection_duration = clock.unix_timestamp - state.slippage_protection_timestamp;
-        if protection_duration < SLIPPAGE_PROTECTION_DURATION {
-            // If price fluctuation is still high, return error
-            return Err(SolDripError::SlippageProtectionActive.into());
-        } else {
-            // Deactivate slippage protection
-            state.slippage_protection_active = false;
+    #[test]
+    fn history_keeps_the_newest_rounds() {
+        let mut history = distribution_history();
+        assert_eq!(history.rounds().count(), 0);
+        
+        let rounds = DISTRIBUTION_HISTORY_CAPACITY as u64 + 5;
+        for epoch in 1..=rounds {
+            history.record(RoundSummary { epoch, ..RoundSummary::default() });
         }
+        let epochs: Vec<u64> = history.rounds().map(|summary| summary.epoch).collect();
+        assert_eq!(epochs.len(), DISTRIBUTION_HISTORY_CAPACITY);
+        assert_eq!(epochs[0], rounds);
+        assert_eq!(*epochs.last().unwrap(), rounds - DISTRIBUTION_HISTORY_CAPACITY as u64 + 1);
+        
+        let mut data = Vec::new();
+        history.serialize(&mut data).unwrap();
+        assert_eq!(data.len(), DistributionHistory::LEN);
     }
     
-    // Check if price fluctuation is high
-    // In a real implementation, we would fetch current price from an oracle
-    // Here we're using a placeholder implementation
-    if state.price_fluctuation_bps > PRICE_FLUCTUATION_THRESHOLD_BPS {
-        // Activate slippage protection
-        state.slippage_protection_active = true;
-        state.slippage_protection_timestamp = clock.unix_timestamp;
-        
-        // Use 80% of tax for buyback instead
-        let buyback_amount = total_tax * 80 / 100;
-        let remaining_tax = total_tax - buyback_amount;
+    #[test]
+    fn reward_positions_earn_only_while_holding() {
+        let mut stream = RewardStream { total_position_balance: 1_000, ..reward_stream() };
         
-        // TODO: Implement buyback logic using Jupiter aggregator
-        // For now, we'll just send the tax to the LP pool
+        // One holder with 1_000 tokens, both accounts' positions already open
+        let (mut first, mut second) = (reward_position(1_000), reward_position(0));
+        stream.accrue(500);
         
-        // Transfer remaining tax to dividend pool
-        invoke(
-            &token_instruction::transfer(
-                token_program.key,
-                source.key,
-                dividend_pool.key,
-                sender.key,
-                &[],
-                remaining_tax,
-            )?,
-            &[
-                source.clone(),
-                dividend_pool.clone(),
-                sender.clone(),
-                token_program.clone(),
-            ],
-        )?;
+        // Moving the tokens settles both sides on what they held so far
+        first.settle(&mut stream, 1_000, 0);
+        second.settle(&mut stream, 0, 1_000);
+        assert_eq!(stream.total_position_balance, 1_000);
+        stream.accrue(300);
         
-        msg!("Slippage protection activated: {} tokens used for buyback", buyback_amount);
-    } else {
-        // Normal tax distribution
+        // Claiming on both pays each period once
+        assert_eq!(second.claim(&mut stream, 1_000, u64::MAX), 300);
+        assert_eq!(first.claim(&mut stream, 0, u64::MAX), 500);
+        assert_eq!(first.claim(&mut stream, 0, u64::MAX) + second.claim(&mut stream, 1_000, u64::MAX), 0);
         
-        // Transfer LP tax
-        invoke(
-            &token_instruction::transfer(
-                token_program.key,
-                source.key,
-                lp_pool.key,
-                sender.key,
-                &[],
-                lp_tax,
-            )?,
-            &[
-                source.clone(),
-                lp_pool.clone(),
-                sender.clone(),
-                token_program.clone(),
-            ],
-        )?;
+        // Tokens moved outside the program stop earning on the old account,
+        // whose share is left unallocated, and start earning on the new one
+        // once it is settled
+        stream.accrue(200);
+        assert_eq!(second.pending(&stream, 0), 0);
+        assert_eq!(first.pending(&stream, 1_000), 0);
+        second.settle(&mut stream, 0, 0);
+        first.settle(&mut stream, 1_000, 1_000);
+        assert_eq!(stream.unallocated, 200);
+        assert_eq!(stream.total_position_balance, 1_000);
         
-        // Transfer dividend tax
-        invoke(
-            &token_instruction::transfer(
-                token_program.key,
-                source.key,
-                dividend_pool.key,
-                sender.key,
-                &[],
-                dividend_tax,
-            )?,
-            &[
-                source.clone(),
-                dividend_pool.clone(),
-                sender.clone(),
-                token_program.clone(),
-            ],
-        )?;
+        // Claims never take more than the vault holds; the rest stays owed
+        stream.accrue(100);
+        assert_eq!(first.claim(&mut stream, 1_000, 60), 60);
+        assert_eq!(first.claim(&mut stream, 1_000, u64::MAX), 40);
     }
     
-    // Transfer remaining tokens to destination
-    invoke(
-        &token_instruction::transfer(
-            token_program.key,
-            source.key,
-            destination.key,
-            sender.key,
-            &[],
-            transfer_amount,
-        )?,
-        &[
-            source.clone(),
-            destination.clone(),
-            sender.clone(),
-            token_program.clone(),
-        ],
-    )?;
-    
-    // Save updated state
-    state.serialize(&mut *state_account.data.borrow_mut())?;
+    #[test]
+    fn rewards_are_shared_among_open_positions_only() {
+        let mut stream = reward_stream();
+        
+        // Funded before any position opened, nobody can claim it
+        stream.accrue(300);
+        assert_eq!((stream.reward_per_token, stream.unallocated, stream.total_funded), (0, 300, 300));
+        
+        // A position opened on the first claim earns the whole deposit, however
+        // many tokens sit in accounts that never claimed
+        let mut position = RewardPosition { reward_per_token_paid: stream.reward_per_token, ..reward_position(500) };
+        stream.total_position_balance += position.balance;
+        stream.accrue(100);
+        assert_eq!(position.claim(&mut stream, 500, u64::MAX), 100);
+        assert_eq!(stream.unallocated, 300);
+    }
     
-    msg!("Transferred {} tokens with tax: {} LP, {} dividend", transfer_amount, lp_tax, dividend_tax);
+    #[test]
+    fn burned_tokens_leave_the_dividend_denominator() {
+        let mut state = test_state();
+        state.total_supply = 1_000;
+        state.total_burned = 500;
+        assert_eq!(state.circulating_supply(), 500);
+        
+        // Burns never push the circulating supply below zero
+        state.total_burned = 2_000;
+        assert_eq!(state.circulating_supply(), 0);
+    }
     
-    Ok(())
-}
-
-// Distribute dividends to token holders
-fn process_distribute_dividends(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
+    #[test]
+    fn sell_cooldown_follows_the_owner_across_token_accounts() {
+        let (program_id, state, owner) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (address, bump) = find_seller_record_address(&program_id, &state, &owner);
+        let mut record = SellerRecord { state, owner, last_sell_timestamp: 0, bump };
+        
+        // Sell from the first token account
+        assert_eq!(record.record_sell(60, 1_000), Ok(()));
+        
+        // Moving the tokens to a second account of the same wallet and
+        // selling from there hits the same record
+        assert_eq!(find_seller_record_address(&program_id, &state, &owner), (address, bump));
+        assert_eq!(record.record_sell(60, 1_030), Err(SolDripError::SellCooldownActive));
+        assert_eq!(record.last_sell_timestamp, 1_000);
+        
+        // Another wallet has its own record
+        assert_ne!(find_seller_record_address(&program_id, &state, &Pubkey::new_unique()).0, address);
+        
+        assert_eq!(record.record_sell(60, 1_060), Ok(()));
+        assert_eq!(record.record_sell(0, 1_061), Ok(()));
+    }
     
-    // Get accounts
-    let distributor = next_account_info(account_info_iter)?;
-    let dividend_pool = next_account_info(account_info_iter)?;
-    let state_account = next_account_info(account_info_iter)?;
-    let clock_account = next_account_info(account_info_iter)?;
-    let token_program = next_account_info(account_info_iter)?;
+    #[test]
+    fn crank_reward_is_paid_per_holder_and_the_rest_returned() {
+        let mut round = DistributionRound {
+            state: Pubkey::default(),
+            round_id: 3,
+            is_active: true,
+            distributable_lamports: 98 * CRANK_REWARD_PER_HOLDER,
+            total_supply: 0,
+            total_weight: 0,
+            distributed_weight: 0,
+            distributed_lamports: 0,
+            crank_reward_lamports: 2 * CRANK_REWARD_PER_HOLDER + 1,
+            crank_rewards_paid: 0,
+            holders_paid: 0,
+            eligible_supply: 0,
+            started_at: 0,
+            started_by: Pubkey::default(),
+            bump: 0,
+        };
+        
+        // Paid by holder count, however much each holder received
+        assert_eq!(round.crank_reward(0), 0);
+        assert_eq!(round.crank_reward(1), CRANK_REWARD_PER_HOLDER);
+        
+        // Never more than is left of the reserve
+        round.crank_rewards_paid = round.crank_reward(1);
+        assert_eq!(round.crank_reward(5), CRANK_REWARD_PER_HOLDER + 1);
+        
+        // The unused reserve is recorded as returned to the pool
+        let summary = round.summary(60);
+        assert_eq!(summary.crank_rewards_paid, CRANK_REWARD_PER_HOLDER);
+        assert_eq!(summary.crank_reserve_returned, CRANK_REWARD_PER_HOLDER + 1);
+        assert_eq!(summary.epoch, 3);
+    }
     
-    // Verify signer
-    if !distributor.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
+    #[test]
+    fn distributions_are_triggered_by_threshold_or_age() {
+        let config = SolDripConfig::default();
+        let last = 1_700_000_000;
+        let interval = config.min_distribution_interval;
+        let forced_after = config.force_distribution_after;
+        
+        // Never more often than the minimum interval, however full the pool
+        assert_eq!(config.distribution_due(DISTRIBUTION_THRESHOLD * 10, last, last + interval - 1), Err(SolDripError::DistributionTooSoon));
+        assert_eq!(config.distribution_due(DISTRIBUTION_THRESHOLD, last, last + interval), Ok(()));
+        
+        // Below the threshold only once the pool has waited long enough
+        assert_eq!(config.distribution_due(DISTRIBUTION_THRESHOLD - 1, last, last + interval), Err(SolDripError::InsufficientSolForDistribution));
+        assert_eq!(config.distribution_due(DISTRIBUTION_THRESHOLD - 1, last, last + forced_after - 1), Err(SolDripError::InsufficientSolForDistribution));
+        assert_eq!(config.distribution_due(1, last, last + forced_after), Ok(()));
+        
+        // An empty pool is never distributed, and a forced trigger can be turned off
+        assert_eq!(config.distribution_due(0, last, last + forced_after), Err(SolDripError::InsufficientSolForDistribution));
+        let config = SolDripConfig { force_distribution_after: 0, ..config };
+        assert_eq!(config.distribution_due(1, last, i64::MAX), Err(SolDripError::InsufficientSolForDistribution));
     }
     
-    // Deserialize state
-    let mut state = SolDripState::try_from_slice(&state_account.data.borrow())?;
+    #[test]
+    fn compounding_is_capped_above_the_maximum_holding() {
+        let mut state = test_state();
+        state.total_supply = 1_000_000;
+        
+        // The default cap lets compounding grow a holding past the 3% maximum holding, up to 5%
+        assert_eq!(state.compound_holding_limit(), 50_000);
+        
+        // A cap below the maximum holding never lowers it
+        state.config.max_compound_holding_bps = 100;
+        assert_eq!(state.compound_holding_limit(), bps_of(state.total_supply, MAX_HOLDING_BPS));
+    }
     
-    // Get clock
-    let clock = Clock::from_account_info(clock_account)?;
+    #[test]
+    fn launch_tax_decays_to_the_tier_tax() {
+        let config = SolDripConfig::default();
+        let launch_slot = 1_000;
+        assert_eq!(config.launch_tax_bps(launch_slot, launch_slot), Some(DEFAULT_LAUNCH_TAX_BPS));
+        assert_eq!(config.launch_tax_bps(launch_slot, launch_slot + 75), Some(1_500));
+        assert_eq!(config.launch_tax_bps(launch_slot, launch_slot + DEFAULT_LAUNCH_TAX_SLOTS), None);
+        
+        let mut state = test_state();
+        state.total_supply = 1_000_000_000;
+        state.launch_slot = launch_slot;
+        let small = 1_000;
+        let large = 5_000_000;
+        
+        // No launch tax before trading opens
+        assert_eq!(state.transfer_tax_tier(small, launch_slot).1, state.config.tax_tiers[0]);
+        
+        // The excess over the tier's own tax goes to dividends
+        state.trading_enabled = true;
+        let (index, tier) = state.transfer_tax_tier(small, launch_slot);
+        assert_eq!(index, 0);
+        assert_eq!(tier.total_bps(), DEFAULT_LAUNCH_TAX_BPS);
+        assert_eq!(tier.lp_bps, LP_TAX_BPS);
+        
+        // A tier taxed above the decayed launch tax keeps its own tax
+        let late = launch_slot + 140;
+        assert!(state.transfer_tax_tier(small, late).1.total_bps() > TAX_BPS);
+        assert_eq!(state.transfer_tax_tier(large, late), (4, state.config.tax_tiers[4]));
+    }
     
-    // Check if dividend pool has enough SOL
-    if dividend_pool.lamports() < DISTRIBUTION_THRESHOLD {
-        return Err(SolDripError::InsufficientSolForDistribution.into());
+    #[test]
+    fn launch_tax_is_capped_at_the_whole_transfer() {
+        let mut config = SolDripConfig { launch_tax_bps: BPS_DENOMINATOR as u16, ..SolDripConfig::default() };
+        assert!(config.validate().is_ok());
+        config.launch_tax_bps = BPS_DENOMINATOR as u16 + 1;
+        assert_eq!(config.validate(), Err(SolDripError::InvalidConfig));
     }
     
-    // Check if slippage protection is active
-    if state.slippage_protection_active {
-        let protection_duration = clock.unix_timestamp - state.slippage_protection_timestamp;
-        if protection_duration < SLIPPAGE_PROTECTION_DURATION {
-            // If slippage protection is active, return error
-            return Err(SolDripError::SlippageProtectionActive.into());
-        } else {
-            // Deactivate slippage protection
-            state.slippage_protection_active = false;
-        }
+    #[test]
+    fn treasury_withdraw_period_must_be_positive() {
+        let mut config = SolDripConfig { treasury_withdraw_period: 0, ..SolDripConfig::default() };
+        assert_eq!(config.validate(), Err(SolDripError::InvalidConfig));
+        config.treasury_withdraw_period = -1;
+        assert_eq!(config.validate(), Err(SolDripError::InvalidConfig));
     }
     
-    // Get distributable lamports (minus gas costs)
-    let distributable_lamports = dividend_pool.lamports() * (100 - GAS_COST_PERCENTAGE) as u64 / 100;
+    #[test]
+    fn treasury_withdrawals_are_limited_per_period() {
+        let mut state = test_state();
+        state.config.treasury_withdraw_limit = 1_000;
+        let period = state.config.treasury_withdraw_period;
+        let start = 10 * period;
+        
+        // The first withdrawal opens a period
+        assert_eq!(state.record_treasury_withdrawal(600, start), Ok(()));
+        assert_eq!(state.treasury_period_start, start);
+        
+        // The limit covers the whole period; a rejected withdrawal counts nothing
+        assert_eq!(state.record_treasury_withdrawal(401, start + period - 1), Err(SolDripError::TreasuryWithdrawalLimitExceeded));
+        assert_eq!(state.record_treasury_withdrawal(400, start + period - 1), Ok(()));
+        assert_eq!(state.treasury_withdrawn_in_period, 1_000);
+        
+        // Once the period has elapsed the limit is available again
+        assert_eq!(state.record_treasury_withdrawal(1_000, start + period), Ok(()));
+        assert_eq!(state.treasury_period_start, start + period);
+        assert_eq!(state.treasury_withdrawn_in_period, 1_000);
+        assert_eq!(state.total_treasury_withdrawn, 2_000);
+        
+        // A rejected withdrawal in a new period does not roll the period over
+        assert_eq!(state.record_treasury_withdrawal(1_001, start + 3 * period), Err(SolDripError::TreasuryWithdrawalLimitExceeded));
+        assert_eq!(state.treasury_period_start, start + period);
+        assert_eq!(state.record_treasury_withdrawal(u64::MAX, start + 3 * period), Err(SolDripError::TreasuryWithdrawalLimitExceeded));
+    }
     
-    // Calculate total token supply from token accounts
-    // For simplicity, we're using the state's total_supply
-    let total_supply = state.total_supply;
+    #[test]
+    fn treasury_withdraw_limit_of_zero_blocks_withdrawals() {
+        let mut state = test_state();
+        assert_eq!(state.config.treasury_withdraw_limit, 0);
+        assert_eq!(state.record_treasury_withdrawal(1, 0), Err(SolDripError::TreasuryWithdrawalLimitExceeded));
+        assert_eq!(state.record_treasury_withdrawal(0, 0), Ok(()));
+    }
     
-    // Get token holder accounts
-    let token_holders: Vec<&AccountInfo> = account_info_iter.collect();
+    #[test]
+    fn min_distribution_interval_must_not_be_negative() {
+        let mut config = SolDripConfig { min_distribution_interval: 0, ..SolDripConfig::default() };
+        assert!(config.validate().is_ok());
+        config.min_distribution_interval = -1;
+        assert_eq!(config.validate(), Err(SolDripError::InvalidConfig));
+    }
     
-    // Track total distributed
-    let mut total_distributed: u64 = 0;
+    #[test]
+    fn tiers_are_selected_by_size() {
+        let config = SolDripConfig::default();
+        let supply = 1_000_000_000;
+        assert_eq!(config.tax_tier(0, supply).min_amount_bps, 0);
+        assert_eq!(config.tax_tier(499_999, supply).min_amount_bps, 0);
+        assert_eq!(config.tax_tier(500_000, supply).min_amount_bps, 5);
+        assert_eq!(config.tax_tier(1_000_000, supply).min_amount_bps, 10);
+        assert_eq!(config.tax_tier(2_000_000, supply).min_amount_bps, LARGE_SALE_THRESHOLD_BPS);
+        assert_eq!(config.tax_tier(5_000_000, supply).min_amount_bps, 50);
+        assert!(config.validate().is_ok());
+    }
     
-    // Distribute to each token holder
-    for token_holder in token_holders {
-        // Verify it's a token account
-        if !is_token_account(token_holder, token_program.key) {
-            continue;
-        }
+    #[test]
+    fn percentages_are_applied_in_basis_points() {
+        let supply = 1_000_000_000;
         
-        // Load token account data
-        let token_data = match TokenAccount::unpack(&token_holder.data.borrow()) {
-            Ok(data) => data,
-            Err(_) => continue,
-        };
+        // The large-sale threshold is 0.2% of supply, not 2%
+        assert_eq!(bps_of(supply, LARGE_SALE_THRESHOLD_BPS), 2_000_000);
+        let config = SolDripConfig::default();
+        assert_eq!(config.tax_tier_index(1_999_999, supply), 2);
+        assert_eq!(config.tax_tier_index(2_000_000, supply), 3);
         
-        // Skip if token account doesn't hold SolDrip tokens
-        if token_data.mint != state.mint {
-            continue;
-        }
+        // Fractional percentages such as 4.5% are expressible
+        let tier = TaxTier { min_amount_bps: 0, lp_bps: 100, dividend_bps: 350, burn_bps: 0, treasury_bps: 0 };
+        let split = tier.split(10_000);
+        assert_eq!((split.transfer_amount, split.lp, split.dividend), (9_550, 100, 350));
         
-        // Calculate dividend amount
-        // In a real implementation, we would track holding time
-        // Here we're using a placeholder implementation
-        let holding_days = Some(10); // Placeholder: assume 10 days
-        let dividend_amount = calculate_dividend_share(
-            token_data.amount,
-            total_supply,
-            distributable_lamports,
-            holding_days,
-        );
+        // Raw amounts times 10_000 do not overflow
+        assert_eq!(bps_of(u64::MAX, BPS_DENOMINATOR as u16), u64::MAX);
         
-        if dividend_amount > 0 {
-            // Transfer dividend as SOL
-            // In a real implementation, we would use system_instruction::transfer
-            // Here we're using a placeholder implementation
-            **dividend_pool.try_borrow_mut_lamports()? -= dividend_amount;
-            **token_holder.try_borrow_mut_lamports()? += dividend_amount;
-            
-            total_distributed += dividend_amount;
-            
-            msg!("Distributed {} SOL to token holder {}", 
-                 dividend_amount as f64 / 1_000_000_000.0, 
-                 token_holder.key);
-        }
+        // The maximum holding is 3% of supply
+        let mut state = test_state();
+        state.total_supply = supply;
+        state.treasury_vault = Pubkey::new_unique();
+        let transfer = TransferCheck {
+            source: Pubkey::new_unique(),
+            destination: Pubkey::new_unique(),
+            amount: 30_000_000,
+            source_balance: 30_000_000,
+            destination_balance: 0,
+            split: TaxSplit { transfer_amount: 30_000_000, ..TaxSplit::default() },
+            exempt_from_limits: false,
+            last_sell_timestamp: None,
+        };
+        assert!(!state.transfer_violations(&transfer, 0).exceeds_max_holding);
+        let over = TransferCheck { destination_balance: 1, ..transfer };
+        assert!(state.transfer_violations(&over, 0).exceeds_max_holding);
     }
     
-    // Update state
-    state.total_sol_distributed += total_distributed;
-    state.last_distribution_timestamp = clock.unix_timestamp;
-    state.serialize(&mut *state_account.data.borrow_mut())?;
-    
-    msg!("Total distributed: {} SOL", total_distributed as f64 / 1_000_000_000.0);
+    #[test]
+    fn tiers_past_the_tier_count_are_ignored() {
+        let config = SolDripConfig { tax_tier_count: 2, ..SolDripConfig::default() };
+        let supply = 1_000_000_000;
+        assert_eq!(config.tax_tier_index(5_000_000, supply), 1);
+        assert_eq!(config.tax_tier_index(u64::MAX, supply), 1);
+        assert!(config.validate().is_ok());
+    }
     
-    Ok(())
-}
-
-// Helper function to check if an account is a token account
-fn is_token_account(account: &AccountInfo, token_program_id: &Pubkey) -> bool {
-    // Check if account is owned by the token program
-    account.owner == token_program_id
-}
-
-// Helper function to calculate dividend share
-fn calculate_dividend_share(
-    token_balance: u64, 
-    total_supply: u64, 
-    distributable_lamports: u64,
-    holding_days: Option<u64>,
-) -> u64 {
-    if token_balance == 0 || total_supply == 0 {
-        return 0;
+    #[test]
+    fn invalid_tier_schedules_are_rejected() {
+        let default = SolDripConfig::default();
+        let mut no_tiers = SolDripConfig { tax_tier_count: 0, ..default.clone() };
+        assert_eq!(no_tiers.validate(), Err(SolDripError::InvalidTaxTiers));
+        no_tiers.tax_tier_count = MAX_TAX_TIERS as u8 + 1;
+        assert_eq!(no_tiers.validate(), Err(SolDripError::InvalidTaxTiers));
+        
+        // The first tier must cover every amount
+        let mut tiers = default.clone();
+        tiers.tax_tiers[0].min_amount_bps = 1;
+        assert_eq!(tiers.validate(), Err(SolDripError::InvalidTaxTiers));
+        
+        // Thresholds must rise strictly
+        let mut tiers = default.clone();
+        tiers.tax_tiers[2].min_amount_bps = tiers.tax_tiers[1].min_amount_bps;
+        assert_eq!(tiers.validate(), Err(SolDripError::InvalidTaxTiers));
+        
+        // No tier may tax more than MAX_TIER_TAX_BPS
+        let mut tiers = default;
+        tiers.tax_tiers[3].treasury_bps = MAX_TIER_TAX_BPS - tiers.tax_tiers[3].total_bps();
+        assert!(tiers.validate().is_ok());
+        tiers.tax_tiers[3].treasury_bps += 1;
+        assert_eq!(tiers.validate(), Err(SolDripError::InvalidTaxTiers));
     }
     
-    // Calculate base share
-    let base_share = token_balance * distributable_lamports / total_supply;
+    #[test]
+    fn merkle_proofs_verify_against_root() {
+        let holders: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let leaves: Vec<[u8; 32]> = holders
+            .iter()
+            .enumerate()
+            .map(|(index, holder)| merkle_leaf(index as u32, holder, 1_000 * (index as u64 + 1)))
+            .collect();
+        // The third leaf has no sibling and is carried up unchanged
+        let left = merkle_parent(&leaves[0], &leaves[1]);
+        let root = merkle_parent(&left, &leaves[2]);
+        
+        assert!(verify_merkle_proof(&root, leaves[0], &[leaves[1], leaves[2]]));
+        assert!(verify_merkle_proof(&root, leaves[1], &[leaves[0], leaves[2]]));
+        assert!(verify_merkle_proof(&root, leaves[2], &[left]));
+        
+        // Wrong amount, index or holder
+        assert!(!verify_merkle_proof(&root, merkle_leaf(0, &holders[0], 1_001), &[leaves[1], leaves[2]]));
+        assert!(!verify_merkle_proof(&root, merkle_leaf(1, &holders[0], 1_000), &[leaves[1], leaves[2]]));
+        assert!(!verify_merkle_proof(&root, merkle_leaf(0, &holders[1], 1_000), &[leaves[1], leaves[2]]));
+    }
     
-    // Apply holding time bonus if applicable
-    if let Some(days) = holding_days {
-        if days >= HOLDING_BONUS_DAYS {
-            // Apply 1.1x bonus for holders of 7+ days
-            return base_share * HOLDING_BONUS_MULTIPLIER / HOLDING_BONUS_DIVISOR;
+    #[test]
+    fn claim_bitmap_flags_each_leaf_once() {
+        let mut data = vec![0u8; MerkleClaimBitmap::LEN];
+        let header = MerkleClaimBitmap { distribution: Pubkey::new_unique(), bitmap_index: 1, bump: 255 };
+        header.serialize(&mut &mut data[..]).unwrap();
+        
+        // Bitmap 1 covers leaves from MERKLE_CLAIMS_PER_BITMAP on
+        let first = MERKLE_CLAIMS_PER_BITMAP;
+        let last = 2 * MERKLE_CLAIMS_PER_BITMAP - 1;
+        for index in [first, first + 7, first + 8, last] {
+            assert!(!MerkleClaimBitmap::is_claimed(&data, index));
+            MerkleClaimBitmap::set_claimed(&mut data, index);
+            assert!(MerkleClaimBitmap::is_claimed(&data, index));
         }
+        assert!(!MerkleClaimBitmap::is_claimed(&data, first + 1));
+        assert_eq!(MerkleClaimBitmap::deserialize(&mut &data[..]).unwrap().bitmap_index, 1);
+        
+        let distribution = MerkleDistribution { claim_count: MERKLE_CLAIMS_PER_BITMAP + 1, ..merkle_distribution() };
+        assert_eq!(distribution.bitmap_count(), 2);
+        assert_eq!(distribution.try_to_vec().unwrap().len(), MerkleDistribution::LEN);
     }
     
-    base_share
-}
-
-// Helper function to check if price fluctuation is above threshold
-fn is_price_fluctuation_above_threshold(current_price: u64, previous_price: u64) -> bool {
-    if previous_price == 0 {
-        return false;
+    #[test]
+    fn merkle_distribution_is_recorded_and_expires() {
+        let published_at = 1_700_000_000;
+        let mut distribution = MerkleDistribution {
+            distribution_id: 3,
+            epoch: 7,
+            total_amount: 5_000_000_000,
+            claimed_amount: 1_500_000_000,
+            claim_count: 40,
+            claims_made: 12,
+            published_at,
+            publisher: Pubkey::new_unique(),
+            expires_at: published_at + MERKLE_CLAIM_PERIOD,
+            ..merkle_distribution()
+        };
+        
+        // The history records the reserved amount under the opened epoch
+        let mut history = DistributionHistory { state: distribution.state, ..distribution_history() };
+        history.record(distribution.summary());
+        let recorded = history.rounds().next().unwrap();
+        assert_eq!((recorded.epoch, recorded.distributed_lamports, recorded.holders_paid), (7, 5_000_000_000, 40));
+        assert_eq!(recorded.crank, distribution.publisher);
+        
+        assert!(!distribution.is_expired(distribution.expires_at - 1));
+        assert!(distribution.is_expired(distribution.expires_at));
+        
+        // Only the unclaimed part returns to the pool, once
+        assert_eq!(distribution.unclaimed_amount(), 3_500_000_000);
+        distribution.reclaimed = true;
+        assert_eq!(distribution.unclaimed_amount(), 0);
     }
     
-    // Calculate fluctuation in basis points (1/100 of a percent)
-    let fluctuation = if current_price > previous_price {
-        (current_price - previous_price) * 10000 / previous_price
-    } else {
-        (previous_price - current_price) * 10000 / previous_price
-    };
+    #[test]
+    fn transfer_violations_follow_transfer_with_tax() {
+        let mut state = test_state();
+        state.total_supply = 1_000_000;
+        let split = TaxSplit { transfer_amount: 900, lp: 0, dividend: 50, burn: 0, treasury: 50 };
+        let transfer = TransferCheck {
+            source: Pubkey::new_unique(),
+            destination: Pubkey::new_unique(),
+            amount: 1_000,
+            source_balance: 1_000,
+            destination_balance: 0,
+            split,
+            exempt_from_limits: false,
+            last_sell_timestamp: None,
+        };
+        
+        // Only the missing treasury vault rejects it
+        let violations = state.transfer_violations(&transfer, 0);
+        assert_eq!(violations, TransferViolations { treasury_not_initialized: true, ..Default::default() });
+        assert_eq!(violations.first_error(), Some(SolDripError::TreasuryNotInitialized));
+        
+        state.treasury_vault = Pubkey::new_unique();
+        assert_eq!(state.transfer_violations(&transfer, 0).first_error(), None);
+        
+        // A paused program fails before an insufficient balance
+        state.paused = true;
+        let short = TransferCheck { source_balance: 999, ..transfer };
+        let violations = state.transfer_violations(&short, 0);
+        assert!(violations.paused && violations.insufficient_balance);
+        assert_eq!(violations.first_error(), Some(SolDripError::ProgramPaused));
+        
+        state.paused = false;
+        assert_eq!(
+            state.transfer_violations(&short, 0).first_error(),
+            Some(SolDripError::InsufficientTokenBalance)
+        );
+    }
     
-    fluctuation as u16 > PRICE_FLUCTUATION_THRESHOLD_BPS
+    #[test]
+    fn sells_are_not_held_to_the_maximum_holding() {
+        let mut state = test_state();
+        state.total_supply = 1_000_000;
+        state.config.market_token_account = Pubkey::new_unique();
+        let transfer = TransferCheck {
+            source: Pubkey::new_unique(),
+            destination: state.config.market_token_account,
+            amount: 1_000,
+            source_balance: 1_000,
+            destination_balance: 500_000,
+            split: TaxSplit { transfer_amount: 1_000, ..TaxSplit::default() },
+            exempt_from_limits: false,
+            last_sell_timestamp: None,
+        };
+        assert!(!state.transfer_violations(&transfer, 0).exceeds_max_holding);
+        
+        // Any other destination past 3% of supply is still rejected
+        let transfer = TransferCheck { destination: Pubkey::new_unique(), ..transfer };
+        assert!(state.transfer_violations(&transfer, 0).exceeds_max_holding);
+    }
 }
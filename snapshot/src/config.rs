@@ -0,0 +1,44 @@
+// Snapshot configuration, read from command-line flags or environment variables
+
+use clap::Parser;
+use solana_sdk::pubkey::Pubkey;
+use std::path::PathBuf;
+
+#[derive(Parser, Debug, Clone)]
+#[command(name = "soldrip-snapshot", about = "Snapshots SolDrip holders and builds a Merkle distribution")]
+pub struct Config {
+    /// RPC endpoint, e.g. http://127.0.0.1:8899 for solana-test-validator
+    #[arg(long, env = "SOLDRIP_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    pub rpc_url: String,
+
+    /// Deployed SolDrip program id
+    #[arg(long, env = "SOLDRIP_PROGRAM_ID")]
+    pub program_id: Pubkey,
+
+    /// SolDrip state account
+    #[arg(long, env = "SOLDRIP_STATE")]
+    pub state: Pubkey,
+
+    /// Read accounts from JSON dumps instead of scanning through RPC; the
+    /// dumps must hold the state account, token accounts and holder records
+    #[arg(long = "dump")]
+    pub dumps: Vec<PathBuf>,
+
+    /// Unix time coin ages are measured at; defaults to the cluster clock,
+    /// or the local clock when reading dumps
+    #[arg(long)]
+    pub timestamp: Option<i64>,
+
+    /// Lamports to distribute; defaults to the state's pool balance
+    #[arg(long)]
+    pub amount: Option<u64>,
+
+    /// Token account or owner wallet to exclude, in addition to the DEX pool
+    /// and the treasury vault; may be repeated
+    #[arg(long = "exclude")]
+    pub exclusions: Vec<Pubkey>,
+
+    /// Output file, or `-` for stdout
+    #[arg(long, default_value = "merkle-distribution.json")]
+    pub out: PathBuf,
+}
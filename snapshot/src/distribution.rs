@@ -0,0 +1,116 @@
+// A Merkle distribution ready for `PublishMerkleRoot`, with every holder's
+// claim and proof, serialized as JSON
+
+use crate::{error::SnapshotError, merkle::MerkleTree, shares::HolderShare, snapshot::Snapshot};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use soldrip::merkle_leaf;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleDistributionFile {
+    pub program_id: String,
+    pub state: String,
+
+    /// Id the distribution gets when published next
+    pub distribution_id: u64,
+
+    /// Distribution epoch eligible balances were taken for
+    pub epoch: u64,
+
+    /// Time coin ages were measured at
+    pub timestamp: i64,
+
+    /// Hex-encoded root, the `merkle_root` argument of `PublishMerkleRoot`
+    pub merkle_root: String,
+
+    /// Lamports owed to all claims together
+    pub total_amount: u64,
+    pub claim_count: u32,
+    pub claims: Vec<Claim>,
+}
+
+// One leaf: everything owed to a recipient wallet
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Claim {
+    pub index: u32,
+    pub holder: String,
+    pub amount: u64,
+
+    /// Hex-encoded sibling hashes from the leaf up to the root
+    pub proof: Vec<String>,
+
+    /// The token accounts the amount was earned by
+    pub sources: Vec<ClaimSource>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimSource {
+    pub token_account: String,
+    pub eligible_balance: u64,
+    pub multiplier_bps: u64,
+    pub amount: u64,
+}
+
+// Merge shares into one leaf per recipient, indexed in ascending recipient
+// order, so the same snapshot always yields the same tree
+pub fn build_distribution(program_id: &Pubkey, snapshot: &Snapshot, shares: &[HolderShare]) -> Result<MerkleDistributionFile, SnapshotError> {
+    let mut by_recipient: BTreeMap<Pubkey, Vec<&HolderShare>> = BTreeMap::new();
+    for share in shares {
+        by_recipient.entry(share.recipient).or_default().push(share);
+    }
+    if by_recipient.is_empty() {
+        return Err(SnapshotError::NothingToDistribute);
+    }
+
+    let leaves: Vec<(Pubkey, u64, Vec<&HolderShare>)> = by_recipient
+        .into_iter()
+        .map(|(recipient, shares)| (recipient, shares.iter().map(|share| share.amount).sum(), shares))
+        .collect();
+    let tree = MerkleTree::new(
+        leaves
+            .iter()
+            .enumerate()
+            .map(|(index, (recipient, amount, _))| merkle_leaf(index as u32, recipient, *amount))
+            .collect(),
+    );
+
+    let claims: Vec<Claim> = leaves
+        .iter()
+        .enumerate()
+        .map(|(index, (recipient, amount, shares))| Claim {
+            index: index as u32,
+            holder: recipient.to_string(),
+            amount: *amount,
+            proof: tree.proof(index).iter().map(|hash| to_hex(hash)).collect(),
+            sources: shares
+                .iter()
+                .map(|share| ClaimSource {
+                    token_account: share.token_account.to_string(),
+                    eligible_balance: share.eligible_balance,
+                    multiplier_bps: share.multiplier_bps,
+                    amount: share.amount,
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(MerkleDistributionFile {
+        program_id: program_id.to_string(),
+        state: snapshot.state_address.to_string(),
+        distribution_id: snapshot.state.merkle_distribution_count + 1,
+        epoch: snapshot.epoch(),
+        timestamp: snapshot.timestamp,
+        merkle_root: to_hex(&tree.root()),
+        total_amount: claims.iter().map(|claim| claim.amount).sum(),
+        claim_count: claims.len() as u32,
+        claims,
+    })
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
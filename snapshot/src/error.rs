@@ -0,0 +1,30 @@
+// Snapshot error type
+
+use solana_client::client_error::ClientError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotError {
+    #[error("RPC error: {0}")]
+    Rpc(Box<ClientError>),
+
+    #[error("Failed to access {0}: {1}")]
+    Io(String, std::io::Error),
+
+    #[error("Invalid account dump {0}: {1}")]
+    InvalidDump(String, String),
+
+    #[error("Account {0} not found")]
+    AccountNotFound(String),
+
+    #[error("Failed to decode account {0}")]
+    InvalidAccountData(String),
+
+    #[error("No holder is owed anything")]
+    NothingToDistribute,
+}
+
+impl From<ClientError> for SnapshotError {
+    fn from(e: ClientError) -> Self {
+        SnapshotError::Rpc(Box::new(e))
+    }
+}
@@ -0,0 +1,11 @@
+// SolDrip snapshot
+// Enumerates the holders of a SolDrip mint, computes their dividend shares
+// the way distribution rounds do, and builds the Merkle tree and proofs for
+// `PublishMerkleRoot` and `ClaimMerkleDividend`
+
+pub mod distribution;
+pub mod error;
+pub mod merkle;
+pub mod shares;
+pub mod snapshot;
+pub mod source;
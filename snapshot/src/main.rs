@@ -0,0 +1,73 @@
+// SolDrip snapshot
+// Writes the Merkle distribution for the current holders of a SolDrip mint
+
+mod config;
+
+use clap::Parser;
+use config::Config;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use soldrip_snapshot::{
+    distribution::build_distribution,
+    error::SnapshotError,
+    shares::{compute_shares, Exclusions},
+    snapshot::Snapshot,
+    source,
+};
+use std::{fs, io::Write, path::Path, process};
+
+fn run(config: Config) -> Result<(), SnapshotError> {
+    let (accounts, timestamp) = if config.dumps.is_empty() {
+        let client = RpcClient::new_with_commitment(config.rpc_url.clone(), CommitmentConfig::confirmed());
+        let accounts = source::fetch_accounts(&client, &config.program_id, &config.state)?;
+        let timestamp = match config.timestamp {
+            Some(timestamp) => timestamp,
+            None => source::fetch_timestamp(&client)?,
+        };
+        (accounts, timestamp)
+    } else {
+        let mut accounts = Vec::new();
+        for path in &config.dumps {
+            accounts.extend(source::load_dump(path)?);
+        }
+        (accounts, config.timestamp.unwrap_or_else(source::system_timestamp))
+    };
+
+    let snapshot = Snapshot::from_accounts(&config.program_id, &config.state, accounts, timestamp)?;
+    let exclusions = Exclusions::new(&snapshot.state, &config.exclusions);
+    let distributable = config.amount.unwrap_or(snapshot.state.pool_balance);
+    let shares = compute_shares(&snapshot, &exclusions, distributable);
+    let distribution = build_distribution(&config.program_id, &snapshot, &shares)?;
+
+    let json = serde_json::to_string_pretty(&distribution).expect("distribution serializes to JSON");
+    write_output(&config.out, &json)?;
+
+    eprintln!(
+        "Merkle distribution {} for epoch {}: {} SOL to {} holders ({} token accounts), root {}",
+        distribution.distribution_id,
+        distribution.epoch,
+        distribution.total_amount as f64 / 1_000_000_000.0,
+        distribution.claim_count,
+        shares.len(),
+        distribution.merkle_root,
+    );
+
+    Ok(())
+}
+
+fn write_output(path: &Path, json: &str) -> Result<(), SnapshotError> {
+    let name = path.display().to_string();
+    let result = if name == "-" {
+        writeln!(std::io::stdout(), "{}", json)
+    } else {
+        fs::write(path, format!("{}\n", json))
+    };
+    result.map_err(|e| SnapshotError::Io(name, e))
+}
+
+fn main() {
+    if let Err(err) = run(Config::parse()) {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,80 @@
+// Merkle tree over distribution leaves, hashed with the program's own
+// `merkle_parent` so its proofs verify in `ClaimMerkleDividend`
+
+use soldrip::merkle_parent;
+
+pub struct MerkleTree {
+    /// Node hashes by level, leaves first and the root last
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    // Build the tree bottom-up; a node without a sibling is carried up unchanged
+    pub fn new(leaves: Vec<[u8; 32]>) -> Self {
+        let mut levels = vec![leaves];
+        while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+            let parents = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => merkle_parent(left, right),
+                    [single] => *single,
+                    _ => unreachable!("chunks of two"),
+                })
+                .collect();
+            levels.push(parents);
+        }
+
+        MerkleTree { levels }
+    }
+
+    // Root hash, all zeroes for an empty tree
+    pub fn root(&self) -> [u8; 32] {
+        self.levels.last().and_then(|level| level.first()).copied().unwrap_or_default()
+    }
+
+    // Sibling hashes from the leaf at `index` up to the root
+    pub fn proof(&self, mut index: usize) -> Vec<[u8; 32]> {
+        let mut proof = Vec::new();
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        proof
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+    use soldrip::{merkle_leaf, verify_merkle_proof};
+
+    #[test]
+    fn proofs_verify_on_chain_for_every_tree_shape() {
+        for count in 1..=17u32 {
+            let claims: Vec<(Pubkey, u64)> = (0..count).map(|index| (Pubkey::new_unique(), index as u64 * 7 + 1)).collect();
+            let leaves: Vec<[u8; 32]> = claims
+                .iter()
+                .enumerate()
+                .map(|(index, (holder, amount))| merkle_leaf(index as u32, holder, *amount))
+                .collect();
+            let tree = MerkleTree::new(leaves.clone());
+
+            for (index, (holder, amount)) in claims.iter().enumerate() {
+                let proof = tree.proof(index);
+                assert!(verify_merkle_proof(&tree.root(), leaves[index], &proof), "leaf {} of {}", index, count);
+                assert!(!verify_merkle_proof(&tree.root(), merkle_leaf(index as u32, holder, amount + 1), &proof));
+            }
+        }
+    }
+
+    #[test]
+    fn single_leaf_is_its_own_root() {
+        let leaf = merkle_leaf(0, &Pubkey::new_unique(), 1);
+        let tree = MerkleTree::new(vec![leaf]);
+        assert_eq!(tree.root(), leaf);
+        assert!(tree.proof(0).is_empty());
+    }
+}
@@ -0,0 +1,217 @@
+// Dividend shares, computed the way a distribution round pays them
+
+use crate::snapshot::Snapshot;
+use solana_sdk::pubkey::Pubkey;
+use soldrip::{calculate_dividend_share, SolDripState};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HolderShare {
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+
+    /// Payout address from the holder record, or the token account owner
+    pub recipient: Pubkey,
+
+    /// Balance the share is paid on
+    pub eligible_balance: u64,
+
+    /// Coin-age multiplier applied to the eligible balance
+    pub multiplier_bps: u64,
+
+    /// Lamports owed
+    pub amount: u64,
+}
+
+// Token accounts, or owners of token accounts, that earn nothing
+pub struct Exclusions {
+    addresses: HashSet<Pubkey>,
+}
+
+impl Exclusions {
+    // The accounts the program excludes from dividends, plus `addresses`
+    pub fn new(state: &SolDripState, addresses: &[Pubkey]) -> Self {
+        let mut excluded: HashSet<Pubkey> = addresses.iter().copied().collect();
+        excluded.extend(state.dividend_exclusions());
+        Exclusions { addresses: excluded }
+    }
+
+    pub fn contains(&self, token_account: &Pubkey, owner: &Pubkey) -> bool {
+        self.addresses.contains(token_account) || self.addresses.contains(owner)
+    }
+}
+
+// Every holder's share of `distributable_lamports`, in token account order.
+// Shares are taken of the total weight counted by holder records, as in a
+// round: token accounts without a record earn nothing, and excluded or
+// ineligible balances leave their share in the pool rather than raising
+// everyone else's.
+pub fn compute_shares(snapshot: &Snapshot, exclusions: &Exclusions, distributable_lamports: u64) -> Vec<HolderShare> {
+    let state = &snapshot.state;
+    let epoch = snapshot.epoch();
+    let total_weight = state.total_weight;
+
+    let mut distributed_weight: u128 = 0;
+    let mut shares = Vec::new();
+    for (token_account, token) in &snapshot.token_accounts {
+        if exclusions.contains(token_account, &token.owner) {
+            continue;
+        }
+        let record = match snapshot.holder_records.get(token_account) {
+            Some(record) => record,
+            None => continue,
+        };
+
+        let (eligible_balance, weight) = record.payout_weight(&state.config, epoch, token.amount);
+        let weight = weight.min(total_weight - distributed_weight);
+        let amount = calculate_dividend_share(weight, total_weight, distributable_lamports);
+        distributed_weight += weight;
+
+        if amount > 0 {
+            shares.push(HolderShare {
+                token_account: *token_account,
+                owner: token.owner,
+                recipient: record.payout_recipient(&token.owner),
+                eligible_balance,
+                multiplier_bps: record.weight_multiplier_bps,
+                amount,
+            });
+        }
+    }
+
+    shares
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshDeserialize;
+    use soldrip::{HolderRecord, SolDripConfig};
+    use spl_token::state::{Account as TokenAccount, AccountState};
+    use std::collections::HashMap;
+
+    const BASE_MULTIPLIER_BPS: u64 = 10_000;
+
+    struct Holder {
+        token_account: Pubkey,
+        owner: Pubkey,
+        balance: u64,
+        multiplier_bps: u64,
+        has_record: bool,
+    }
+
+    fn holder(balance: u64, multiplier_bps: u64) -> Holder {
+        Holder {
+            token_account: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            balance,
+            multiplier_bps,
+            has_record: true,
+        }
+    }
+
+    // Snapshot of `holders`, each counted in the total weight at its balance
+    // and multiplier
+    fn snapshot(holders: &[Holder], snapshot_eligibility: bool) -> Snapshot {
+        let mut state = SolDripState::try_from_slice(&[0u8; SolDripState::LEN]).unwrap();
+        state.config = SolDripConfig { snapshot_eligibility, ..SolDripConfig::default() };
+        state.mint = Pubkey::new_unique();
+        state.distribution_epoch = 4;
+
+        let mut token_accounts = Vec::new();
+        let mut holder_records = HashMap::new();
+        for holder in holders {
+            token_accounts.push((
+                holder.token_account,
+                TokenAccount {
+                    mint: state.mint,
+                    owner: holder.owner,
+                    amount: holder.balance,
+                    state: AccountState::Initialized,
+                    ..TokenAccount::default()
+                },
+            ));
+            if !holder.has_record {
+                continue;
+            }
+            // Held unchanged through the epoch before the snapshot
+            let record = HolderRecord {
+                state: Pubkey::default(),
+                token_account: holder.token_account,
+                payout_address: Pubkey::default(),
+                bump: 0,
+                compound: false,
+                accrued_lamports: 0,
+                exempt_from_limits: false,
+                holding_since: 0,
+                balance_epoch: state.distribution_epoch,
+                epoch_min_balance: holder.balance,
+                previous_epoch_min_balance: holder.balance,
+                weight_balance: holder.balance,
+                weight_multiplier_bps: holder.multiplier_bps,
+                paid_round: 0,
+                weight_epoch: 0,
+                epoch_start_weight: 0,
+            };
+            state.total_weight += record.counted_weight();
+            holder_records.insert(holder.token_account, record);
+        }
+        token_accounts.sort_by_key(|(address, _)| *address);
+
+        Snapshot {
+            state_address: Pubkey::new_unique(),
+            state,
+            timestamp: 0,
+            token_accounts,
+            holder_records,
+        }
+    }
+
+    fn amount_of(shares: &[HolderShare], holder: &Holder) -> Option<u64> {
+        shares.iter().find(|share| share.token_account == holder.token_account).map(|share| share.amount)
+    }
+
+    #[test]
+    fn excluded_accounts_and_owners_leave_their_share_in_the_pool() {
+        let holders = [
+            holder(1_000, BASE_MULTIPLIER_BPS),
+            holder(1_000, BASE_MULTIPLIER_BPS),
+            holder(1_000, BASE_MULTIPLIER_BPS),
+            holder(1_000, BASE_MULTIPLIER_BPS),
+        ];
+        let snapshot = snapshot(&holders, false);
+        let exclusions = Exclusions::new(&snapshot.state, &[holders[1].token_account, holders[2].owner]);
+
+        let shares = compute_shares(&snapshot, &exclusions, 4_000_000);
+        assert_eq!(amount_of(&shares, &holders[0]), Some(1_000_000));
+        assert_eq!(amount_of(&shares, &holders[1]), None);
+        assert_eq!(amount_of(&shares, &holders[2]), None);
+        assert_eq!(amount_of(&shares, &holders[3]), Some(1_000_000));
+    }
+
+    #[test]
+    fn holders_without_a_record_earn_nothing() {
+        let holders = [
+            holder(1_000, BASE_MULTIPLIER_BPS),
+            Holder { has_record: false, ..holder(1_000, BASE_MULTIPLIER_BPS) },
+        ];
+        for snapshot_eligibility in [false, true] {
+            let snapshot = snapshot(&holders, snapshot_eligibility);
+            let shares = compute_shares(&snapshot, &Exclusions::new(&snapshot.state, &[]), 1_000_000);
+            assert_eq!(amount_of(&shares, &holders[0]), Some(1_000_000));
+            assert_eq!(amount_of(&shares, &holders[1]), None, "snapshot eligibility {}", snapshot_eligibility);
+        }
+    }
+
+    #[test]
+    fn coin_age_multiplier_scales_the_share() {
+        let holders = [holder(1_000, BASE_MULTIPLIER_BPS), holder(1_000, 2 * BASE_MULTIPLIER_BPS)];
+        let snapshot = snapshot(&holders, true);
+
+        let shares = compute_shares(&snapshot, &Exclusions::new(&snapshot.state, &[]), 3_000_000);
+        assert_eq!(amount_of(&shares, &holders[0]), Some(1_000_000));
+        assert_eq!(amount_of(&shares, &holders[1]), Some(2_000_000));
+        assert_eq!(shares[1].multiplier_bps, 2 * BASE_MULTIPLIER_BPS);
+        assert!(shares.iter().all(|share| share.eligible_balance == 1_000));
+    }
+}
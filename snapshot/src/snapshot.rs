@@ -0,0 +1,77 @@
+// Holder snapshot of a SolDrip deployment
+
+use crate::error::SnapshotError;
+use borsh::BorshDeserialize;
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
+use soldrip::{find_holder_record_address, HolderRecord, SolDripState};
+use spl_token::state::Account as TokenAccount;
+use std::collections::{BTreeMap, HashMap};
+
+pub struct Snapshot {
+    pub state_address: Pubkey,
+    pub state: SolDripState,
+
+    /// Time coin ages are measured at
+    pub timestamp: i64,
+
+    /// Non-empty token accounts of the mint, in ascending address order
+    pub token_accounts: Vec<(Pubkey, TokenAccount)>,
+
+    /// Holder records by token account
+    pub holder_records: HashMap<Pubkey, HolderRecord>,
+}
+
+impl Snapshot {
+    // Sort scanned or dumped accounts into a snapshot. Accounts of other
+    // mints or states are ignored, and duplicates are read once.
+    pub fn from_accounts(
+        program_id: &Pubkey,
+        state_address: &Pubkey,
+        accounts: Vec<(Pubkey, Account)>,
+        timestamp: i64,
+    ) -> Result<Self, SnapshotError> {
+        let state_account = accounts
+            .iter()
+            .find(|(address, _)| address == state_address)
+            .map(|(_, account)| account)
+            .ok_or_else(|| SnapshotError::AccountNotFound(state_address.to_string()))?;
+        if state_account.owner != *program_id {
+            return Err(SnapshotError::InvalidAccountData(state_address.to_string()));
+        }
+        let state = SolDripState::try_from_slice(&state_account.data)
+            .map_err(|_| SnapshotError::InvalidAccountData(state_address.to_string()))?;
+
+        let mut token_accounts = BTreeMap::new();
+        let mut holder_records = HashMap::new();
+        for (address, account) in accounts {
+            if account.owner == spl_token::id() && account.data.len() == TokenAccount::LEN {
+                if let Ok(token) = TokenAccount::unpack(&account.data) {
+                    if token.mint == state.mint && token.amount > 0 {
+                        token_accounts.insert(address, token);
+                    }
+                }
+            } else if account.owner == *program_id && account.data.len() == HolderRecord::LEN {
+                if let Ok(record) = HolderRecord::try_from_slice(&account.data) {
+                    let (expected, _) = find_holder_record_address(program_id, state_address, &record.token_account);
+                    if record.state == *state_address && address == expected {
+                        holder_records.insert(record.token_account, record);
+                    }
+                }
+            }
+        }
+
+        Ok(Snapshot {
+            state_address: *state_address,
+            state,
+            timestamp,
+            token_accounts: token_accounts.into_iter().collect(),
+            holder_records,
+        })
+    }
+
+    // The distribution epoch a distribution published now opens, which
+    // eligible balances are taken for
+    pub fn epoch(&self) -> u64 {
+        self.state.distribution_epoch + 1
+    }
+}
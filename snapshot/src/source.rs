@@ -0,0 +1,122 @@
+// Loading the accounts a snapshot is built from: scanned live through RPC,
+// or read from JSON dumps of keyed accounts as returned by `getProgramAccounts`
+
+use crate::error::SnapshotError;
+use borsh::BorshDeserialize;
+use serde::Deserialize;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+    rpc_response::RpcKeyedAccount,
+};
+use solana_sdk::{
+    account::{from_account, Account},
+    clock::Clock,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    sysvar,
+};
+use soldrip::{HolderRecord, SolDripState};
+use spl_token::state::Account as TokenAccount;
+use std::{
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// A dump is either the bare account list or the whole JSON-RPC response
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Dump {
+    Response { result: Vec<RpcKeyedAccount> },
+    Accounts(Vec<RpcKeyedAccount>),
+}
+
+// Every account a snapshot needs: the state account, the mint's token
+// accounts and the state's holder records
+pub fn fetch_accounts(client: &RpcClient, program_id: &Pubkey, state_address: &Pubkey) -> Result<Vec<(Pubkey, Account)>, SnapshotError> {
+    let state_account = client
+        .get_account_with_commitment(state_address, client.commitment())?
+        .value
+        .ok_or_else(|| SnapshotError::AccountNotFound(state_address.to_string()))?;
+    let state = SolDripState::try_from_slice(&state_account.data)
+        .map_err(|_| SnapshotError::InvalidAccountData(state_address.to_string()))?;
+
+    let mut accounts = vec![(*state_address, state_account)];
+    accounts.extend(program_accounts(
+        client,
+        &spl_token::id(),
+        vec![
+            RpcFilterType::DataSize(TokenAccount::LEN as u64),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, state.mint.as_ref())),
+        ],
+    )?);
+    accounts.extend(program_accounts(
+        client,
+        program_id,
+        vec![
+            RpcFilterType::DataSize(HolderRecord::LEN as u64),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, state_address.as_ref())),
+        ],
+    )?);
+
+    Ok(accounts)
+}
+
+// Cluster time, for measuring coin age
+pub fn fetch_timestamp(client: &RpcClient) -> Result<i64, SnapshotError> {
+    let account = client
+        .get_account_with_commitment(&sysvar::clock::id(), client.commitment())?
+        .value
+        .ok_or_else(|| SnapshotError::AccountNotFound(sysvar::clock::id().to_string()))?;
+    let clock = from_account::<Clock, _>(&account)
+        .ok_or_else(|| SnapshotError::InvalidAccountData(sysvar::clock::id().to_string()))?;
+
+    Ok(clock.unix_timestamp)
+}
+
+// Local time, for dumps taken just now
+pub fn system_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+// Read a dump file. Account data must be base64 or base58 encoded.
+pub fn load_dump(path: &Path) -> Result<Vec<(Pubkey, Account)>, SnapshotError> {
+    let name = path.display().to_string();
+    let contents = fs::read_to_string(path).map_err(|e| SnapshotError::Io(name.clone(), e))?;
+    let dump: Dump = serde_json::from_str(&contents).map_err(|e| SnapshotError::InvalidDump(name.clone(), e.to_string()))?;
+    let keyed_accounts = match dump {
+        Dump::Response { result } => result,
+        Dump::Accounts(accounts) => accounts,
+    };
+
+    keyed_accounts
+        .into_iter()
+        .map(|keyed| {
+            let invalid = |reason: &str| SnapshotError::InvalidDump(name.clone(), format!("{}: {}", keyed.pubkey, reason));
+            let address: Pubkey = keyed.pubkey.parse().map_err(|_| invalid("invalid address"))?;
+            let account = keyed
+                .account
+                .decode::<Account>()
+                .ok_or_else(|| invalid("account data is not base64 or base58 encoded"))?;
+            Ok((address, account))
+        })
+        .collect()
+}
+
+fn program_accounts(client: &RpcClient, program_id: &Pubkey, filters: Vec<RpcFilterType>) -> Result<Vec<(Pubkey, Account)>, SnapshotError> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(filters),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+
+    Ok(client.get_program_accounts_with_config(program_id, config)?)
+}
@@ -1,12 +1,29 @@
 import * as anchor from '@project-serum/anchor';
-import { Program } from '@project-serum/anchor';
-import { PublicKey, Keypair, Connection, LAMPORTS_PER_SOL } from '@solana/web3.js';
+import { PublicKey, Keypair, Connection, LAMPORTS_PER_SOL, TransactionInstruction, AccountMeta } from '@solana/web3.js';
 import { createMint, getOrCreateAssociatedTokenAccount, mintTo, transfer } from '@solana/spl-token';
 import { assert } from 'chai';
+import idl from '../idl/soldrip.json';
 
 // Program ID will be replaced with the actual deployed program ID
 const PROGRAM_ID = new PublicKey('SoLDripTokenProgramID111111111111111111111111');
 
+// SolDrip is a native program, not an Anchor one: instruction data is the
+// borsh-serialized SolDripInstruction enum, a one-byte variant index followed
+// by the variant's fields. The IDL lists the variants in enum order.
+function instructionTag(name: string): number {
+  const index = idl.instructions.findIndex((instruction) => instruction.name === name);
+  if (index < 0) {
+    throw new Error(`Unknown instruction ${name}`);
+  }
+  return index;
+}
+
+// Build an instruction whose only argument is a u64, e.g. TransferWithTax
+function u64Instruction(name: string, value: anchor.BN, keys: AccountMeta[]): TransactionInstruction {
+  const data = Buffer.concat([Buffer.from([instructionTag(name)]), value.toArrayLike(Buffer, 'le', 8)]);
+  return new TransactionInstruction({ programId: PROGRAM_ID, keys, data });
+}
+
 describe('SolDrip Program Tests', () => {
   // Configure the client to use the local cluster
   const provider = anchor.AnchorProvider.env();
@@ -27,8 +44,6 @@ describe('SolDrip Program Tests', () => {
   const transferAmount = new anchor.BN(10_000 * LAMPORTS_PER_SOL); // 10,000 tokens
   const largeSaleAmount = new anchor.BN(20_000_000 * LAMPORTS_PER_SOL); // 20 million tokens (2% of supply)
 
-  // Mock program for testing
-  let program: Program;
   let connection: Connection;
 
   before(async () => {
@@ -45,10 +60,16 @@ describe('SolDrip Program Tests', () => {
       lastValidBlockHeight: latestBlockhash.lastValidBlockHeight,
       signature: '',
     });
+  });
 
-    // Initialize the program
-    // This is just a placeholder. In real tests, we would use the actual program
-    program = new Program(null, PROGRAM_ID, provider);
+  describe('Instruction Encoding', () => {
+    it('encodes instructions as borsh enum variants', () => {
+      const instruction = u64Instruction('TransferWithTax', transferAmount, []);
+
+      assert.equal(instruction.data[0], 1);
+      assert.equal(instruction.data.length, 9);
+      assert.isTrue(new anchor.BN(instruction.data.subarray(1), 'le').eq(transferAmount));
+    });
   });
 
   describe('Token Initialization', () => {